@@ -19,6 +19,7 @@ struct VertexIn {
     @location(3) border_radius: f32,
     @location(4) color: vec4<f32>,
     @location(5) border_color: vec4<f32>,
+    @location(6) arc: vec2<f32>,
 }
 
 struct VertexOut {
@@ -29,8 +30,11 @@ struct VertexOut {
     @location(3) border_radius: f32,
     @location(4) color: vec4<f32>,
     @location(5) border_color: vec4<f32>,
+    @location(6) arc: vec2<f32>,
 }
 
+const TAU: f32 = 6.28318530718;
+
 //====================================================================
 
 @vertex
@@ -55,27 +59,44 @@ fn vs_main(in: VertexIn) -> VertexOut {
     out.color = in.color;
     out.border_color = in.border_color;
 
+    out.arc = in.arc;
+
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
     let distance = distance(in.pos, in.center);
+    // Width of a single pixel in world space, used to smooth the edges at any zoom level
+    let aa = max(fwidth(distance), 0.0001);
+
+    // Discard anything outside of the arc (start angle, end angle)
+    let arc_length = in.arc.y - in.arc.x;
+    if arc_length < TAU {
+        let offset = in.pos - in.center;
+        let relative_angle = atan2(offset.y, offset.x) - in.arc.x;
+        let angle = relative_angle - floor(relative_angle / TAU) * TAU;
 
-    if distance < in.radius {
-        if in.color.w == 0. {
+        if angle > arc_length {
             discard;
         }
-        return in.color;
     }
-    if distance <= in.radius + in.border_radius {
-        if in.border_color.w == 0. {
-            discard;
-        }
-        return in.border_color;
+
+    let outer_radius = in.radius + in.border_radius;
+
+    // Blend between fill and border, then fade out past the outer edge
+    var color = in.color;
+    if in.border_radius > 0. {
+        let border_amount = smoothstep(in.radius - aa, in.radius, distance);
+        color = mix(in.color, in.border_color, border_amount);
+    }
+    color.w *= 1. - smoothstep(outer_radius - aa, outer_radius, distance);
+
+    if color.w <= 0. {
+        discard;
     }
 
-    discard;
+    return color;
 }
 
 //====================================================================
@@ -0,0 +1,403 @@
+use crate::ik::{self, ForwardKinematic, InverseKinematic, Node, NodeID, NodeManager};
+
+/// Data describing a creature's body and limbs, used to build a `CreatureSubstate`.
+/// Angles are stored in degrees to keep data files readable.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CreatureSpec {
+    pub body: Vec<BodyNodeSpec>,
+    pub body_color: [f32; 4],
+    /// Index of the first body node that is blown around by the wind
+    pub tail_start: usize,
+    pub limbs: Vec<LimbSpec>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BodyNodeSpec {
+    pub radius: f32,
+    /// Overrides the body color for this node
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub color: Option<[f32; 4]>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeSpec {
+    pub radius: f32,
+    /// Min and max rotation in degrees. Uses the node defaults if not set
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub angles: Option<(f32, f32)>,
+    /// Rotation relative to the parent in degrees, eased towards when the limb can't reach
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rest_angle: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LimbSpec {
+    /// Index of the body node the limb is attached to
+    pub parent: usize,
+    pub nodes: Vec<NodeSpec>,
+    /// Rendered radius overrides, indexed from the parent node (0) along the limb
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub widths: Vec<(usize, f32)>,
+    pub reach_range: f32,
+    /// Resting reach angle relative to the parent, in degrees
+    pub reach_angle: f32,
+    pub color: [f32; 4],
+    /// Also create a copy of the limb mirrored to the other side of the body
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mirrored: bool,
+    /// Limb reaches towards the mouse while the left button is held
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub grabs: bool,
+    /// Direction the joints bend towards relative to the parent, in degrees
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pole_angle: Option<f32>,
+}
+
+/// A limb from the spec, ready to be inserted onto the body
+pub struct LimbChain<'a> {
+    pub spec: &'a LimbSpec,
+    /// Body node the limb is attached to
+    pub parent: NodeID,
+    /// Nodes of the limb, not yet inserted
+    pub nodes: Vec<Node>,
+}
+
+impl NodeSpec {
+    #[inline]
+    pub fn to_node(self) -> Node {
+        let node = match self.angles {
+            Some((min, max)) => Node::angles(self.radius, min.to_radians(), max.to_radians()),
+            None => Node::new(self.radius),
+        };
+
+        node.with_rest_rotation(self.rest_angle.to_radians())
+    }
+}
+
+impl CreatureSpec {
+    /// Number of nodes the creature will create, including the body
+    pub fn node_count(&self) -> usize {
+        self.body.len()
+            + self
+                .limbs
+                .iter()
+                .map(|limb| limb.nodes.len() * if limb.mirrored { 2 } else { 1 })
+                .sum::<usize>()
+    }
+
+    /// Number of limbs the creature will create, including mirrored copies
+    pub fn limb_count(&self) -> usize {
+        self.limbs
+            .iter()
+            .map(|limb| if limb.mirrored { 2 } else { 1 })
+            .sum()
+    }
+
+    /// Load the creature shipped in `assets/creatures/default.ron`, falling back to
+    /// the built in definition if it can't be parsed.
+    #[cfg(feature = "serde")]
+    pub fn load_default() -> Self {
+        match Self::from_ron(include_str!("../assets/creatures/default.ron")) {
+            Ok(spec) => spec,
+            Err(e) => {
+                log::warn!("Failed to parse default creature: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    #[cfg(not(feature = "serde"))]
+    #[inline]
+    pub fn load_default() -> Self {
+        Self::default()
+    }
+
+    /// Insert the creature's nodes without any rendering, returning the body and limb chains.
+    /// Each limb chain starts with its parent body node, and mirrored copies come straight
+    /// after the limb they mirror.
+    pub fn insert(
+        &self,
+        node_manager: &mut NodeManager,
+    ) -> (ForwardKinematic, Vec<InverseKinematic>) {
+        let (body, chains) = self.insert_body(node_manager);
+
+        let mut limbs = Vec::new();
+        chains.into_iter().for_each(|chain| {
+            let mirrored = chain.spec.mirrored.then(|| ik::mirror_nodes(&chain.nodes));
+
+            std::iter::once(chain.nodes)
+                .chain(mirrored)
+                .for_each(|nodes| {
+                    let mut limb_nodes = vec![chain.parent];
+                    limb_nodes.extend(node_manager.insert_nodes(&nodes));
+                    limbs.push(InverseKinematic::new(limb_nodes, None, glam::Vec2::ZERO));
+                });
+        });
+
+        (body, limbs)
+    }
+
+    /// Insert only the body nodes, returning the body and the limbs to attach to it.
+    /// Limbs attached past the end of the body are skipped.
+    pub fn insert_body(
+        &self,
+        node_manager: &mut NodeManager,
+    ) -> (ForwardKinematic, Vec<LimbChain<'_>>) {
+        let body_nodes = node_manager.insert_nodes(
+            &self
+                .body
+                .iter()
+                .map(|node| Node::new(node.radius))
+                .collect::<Vec<_>>(),
+        );
+
+        let chains = self
+            .limbs
+            .iter()
+            .filter_map(|limb| {
+                let Some(parent) = body_nodes.get(limb.parent) else {
+                    log::warn!(
+                        "Limb parent {} is out of range of the body ({} nodes)",
+                        limb.parent,
+                        body_nodes.len()
+                    );
+                    return None;
+                };
+
+                Some(LimbChain {
+                    spec: limb,
+                    parent: *parent,
+                    nodes: limb.nodes.iter().copied().map(NodeSpec::to_node).collect(),
+                })
+            })
+            .collect();
+
+        (ForwardKinematic { nodes: body_nodes }, chains)
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn from_ron(data: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::from_str(data)
+    }
+
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_ron(&self) -> Result<String, ron::Error> {
+        ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+    }
+}
+
+impl Default for CreatureSpec {
+    fn default() -> Self {
+        let body_node = |radius: f32, color: Option<[f32; 4]>| BodyNodeSpec { radius, color };
+
+        let limb_nodes = vec![
+            NodeSpec {
+                radius: 20.,
+                angles: Some((90., 90.)),
+                rest_angle: 90.,
+            },
+            NodeSpec {
+                radius: 50.,
+                angles: Some((-50., 180.)),
+                rest_angle: 40.,
+            },
+            NodeSpec {
+                radius: 50.,
+                angles: Some((-50., 180.)),
+                rest_angle: 40.,
+            },
+            NodeSpec {
+                radius: 50.,
+                angles: Some((-50., 180.)),
+                rest_angle: 40.,
+            },
+        ];
+        let limb_widths = vec![(4, 20.), (3, 20.), (2, 25.)];
+        let limb_color = [0.125, 0.412, 0.067, 1.];
+
+        Self {
+            body: vec![
+                body_node(24., None),
+                body_node(30., None),
+                body_node(30., None),
+                body_node(40., None),
+                body_node(45., None),
+                body_node(50., None),
+                //
+                body_node(40., Some([0.2, 0.4, 0.0, 1.])),
+                //
+                body_node(45., Some([0.2, 0.4, 0.0, 1.])),
+                body_node(50., Some([0.2, 0.3, 0.0, 1.])),
+                body_node(40., Some([0.2, 0.3, 0.0, 1.])),
+                body_node(38., Some([0.3, 0.2, 0.0, 1.])),
+                body_node(30., Some([0.3, 0.2, 0.0, 1.])),
+                body_node(22., Some([0.3, 0.2, 0.0, 1.])),
+                body_node(18., Some([0.3, 0.1, 0.0, 1.])),
+                body_node(10., Some([0.2, 0.1, 0.0, 1.])),
+                body_node(10., Some([0.2, 0.1, 0.0, 1.])),
+                body_node(10., Some([0.2, 0.1, 0.0, 1.])),
+                body_node(10., Some([0.2, 0.1, 0.0, 1.])),
+            ],
+            body_color: [0.2, 0.5, 0., 1.],
+            tail_start: 10,
+            limbs: vec![
+                // Arms
+                LimbSpec {
+                    parent: 5,
+                    nodes: limb_nodes.clone(),
+                    widths: limb_widths.clone(),
+                    reach_range: 150.,
+                    reach_angle: -50.,
+                    color: limb_color,
+                    mirrored: true,
+                    grabs: true,
+                    pole_angle: Some(-120.),
+                },
+                // Legs
+                LimbSpec {
+                    parent: 9,
+                    nodes: limb_nodes,
+                    widths: limb_widths,
+                    reach_range: 140.,
+                    reach_angle: -50.,
+                    color: limb_color,
+                    mirrored: true,
+                    grabs: false,
+                    pole_angle: None,
+                },
+            ],
+        }
+    }
+}
+
+/// Generates a creature from a few parameters instead of listing every node
+pub struct CreatureBuilder {
+    pub segment_count: usize,
+    pub base_radius: f32,
+    /// Scales the base radius along the body, from 0 at the head to 1 at the tail
+    pub radius_curve: fn(f32) -> f32,
+    pub body_color: [f32; 4],
+    pub tail_start: usize,
+    /// Limbs and the index of the body segment they attach to
+    pub limb_attachments: Vec<(usize, LimbSpec)>,
+}
+
+impl Default for CreatureBuilder {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            segment_count: 12,
+            base_radius: 40.,
+            radius_curve: |_| 1.,
+            body_color: [0.2, 0.5, 0., 1.],
+            tail_start: 8,
+            limb_attachments: Vec::new(),
+        }
+    }
+}
+
+impl CreatureBuilder {
+    /// Radius of the body segment at the given index
+    pub fn segment_radius(&self, index: usize) -> f32 {
+        let last_index = (self.segment_count.max(2) - 1) as f32;
+        self.base_radius * (self.radius_curve)(index as f32 / last_index)
+    }
+
+    pub fn to_spec(&self) -> CreatureSpec {
+        CreatureSpec {
+            body: (0..self.segment_count)
+                .map(|index| BodyNodeSpec {
+                    radius: self.segment_radius(index),
+                    color: None,
+                })
+                .collect(),
+            body_color: self.body_color,
+            tail_start: self.tail_start,
+            limbs: self
+                .limb_attachments
+                .iter()
+                .map(|(parent, limb)| LimbSpec {
+                    parent: *parent,
+                    ..limb.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// Insert the generated creature's nodes, returning the body and limb chains
+    #[inline]
+    pub fn build(
+        &self,
+        node_manager: &mut NodeManager,
+    ) -> (ForwardKinematic, Vec<InverseKinematic>) {
+        self.to_spec().insert(node_manager)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_spec_matches_built_in_creature() {
+        // 18 body nodes with mirrored pairs of 4 node arms and legs
+        let spec = CreatureSpec::load_default();
+        assert_eq!(spec.node_count(), 18 + 4 * 4);
+        assert_eq!(spec.limb_count(), 4);
+
+        let mut node_manager = NodeManager::new();
+        let (body, limbs) = spec.insert(&mut node_manager);
+        assert_eq!(node_manager.get_values().count(), 34);
+        assert_eq!(body.nodes.len(), 18);
+        assert_eq!(limbs.len(), 4);
+        limbs
+            .iter()
+            .for_each(|limb| assert_eq!(limb.nodes.len(), 5));
+
+        // The shipped file and the built in fallback describe the same creature
+        #[cfg(feature = "serde")]
+        assert_eq!(spec, CreatureSpec::default());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn shipped_spec_parses() {
+        let spec = CreatureSpec::from_ron(include_str!("../assets/creatures/default.ron")).unwrap();
+        let reloaded = CreatureSpec::from_ron(&spec.to_ron().unwrap()).unwrap();
+
+        assert_eq!(reloaded.node_count(), spec.node_count());
+        assert_eq!(reloaded.limb_count(), spec.limb_count());
+    }
+
+    #[test]
+    fn builder_radii_follow_curve() {
+        let builder = CreatureBuilder {
+            segment_count: 10,
+            base_radius: 40.,
+            radius_curve: |t| 1. - t * 0.75,
+            ..Default::default()
+        };
+
+        let mut node_manager = NodeManager::new();
+        let (body, limbs) = builder.build(&mut node_manager);
+        assert_eq!(body.nodes.len(), 10);
+        assert!(limbs.is_empty());
+
+        body.nodes.iter().enumerate().for_each(|(index, id)| {
+            let expected = 40. * (1. - index as f32 / 9. * 0.75);
+            let radius = node_manager.get_node(id).unwrap().radius;
+            assert!(
+                (radius - expected).abs() < 1e-4,
+                "segment {} has radius {}",
+                index,
+                radius
+            );
+        });
+    }
+}
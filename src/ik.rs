@@ -1,18 +1,46 @@
 use core::f32;
 use std::{
-    collections::{hash_map::Values, HashMap},
+    collections::{hash_map::Values, HashMap, HashSet},
     f32::consts::{PI, TAU},
 };
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
     pub radius: f32,
     pub pos: glam::Vec2,
+    /// Distance to this node's child. Uses the radius if not set
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub length: Option<f32>,
 
     // In Radians
     pub rotation: f32,
     pub max_rotation: f32,
     pub min_rotation: f32,
+    /// Rotation relative to the parent that the node eases towards when relaxed
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rest_rotation: f32,
+    /// Depth of the node's polygons. Higher values are drawn in front of lower ones
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub z: f32,
+    /// Overrides the color passed in when building this node's polygons.
+    /// Custom polygon nodes still take precedence
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub color: Option<glam::Vec4>,
+
+    /// How much the node resists bending away from its parent, from 0 (free) to 1 (rigid)
+    pub stiffness: f32,
+    /// How far the node moves towards its attached position each step, from 0 (stays put) to 1 (snaps).
+    /// Lower values make the node drag behind the rest of the chain.
+    pub weight: f32,
+
+    /// Maximum change in rotation per second (in radians)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub max_angular_speed: Option<f32>,
+    /// Rotation after the last angular speed limit was applied.
+    /// Unset until the first limit, so nodes don't swing in from 0 when spawned
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub last_rotation: Option<f32>,
 }
 
 impl Default for Node {
@@ -21,9 +49,17 @@ impl Default for Node {
         Self {
             radius: 80.,
             pos: glam::Vec2::ZERO,
+            length: None,
             rotation: 0.,
             max_rotation: Self::DEFAULT_ANGLE,
             min_rotation: -Self::DEFAULT_ANGLE,
+            rest_rotation: 0.,
+            z: 0.,
+            color: None,
+            stiffness: 0.,
+            weight: 1.,
+            max_angular_speed: None,
+            last_rotation: None,
         }
     }
 }
@@ -77,8 +113,8 @@ impl Node {
         }
     }
 
-    /// Create a new node with the given min and max angles.
-    /// Angles should be in radians.
+    /// Create a new node with the given min and max angles, relative to the parent's rotation.
+    /// Angles should be in radians. See `cone` to give the range as a centre and width instead.
     #[inline]
     pub fn angles(radius: f32, min: f32, max: f32) -> Self {
         Self {
@@ -89,6 +125,83 @@ impl Node {
         }
     }
 
+    /// Create a new node that can rotate `half_width` either side of `center_offset`.
+    /// `cone(radius, 0., angle)` is the same as `angle(radius, angle)`.
+    /// Angles should be in radians.
+    #[inline]
+    pub fn cone(radius: f32, center_offset: f32, half_width: f32) -> Self {
+        let half_width = half_width.abs();
+
+        Self {
+            radius,
+            max_rotation: center_offset + half_width,
+            min_rotation: center_offset - half_width,
+            ..Default::default()
+        }
+    }
+
+    /// Set how much the node resists bending, from 0 (free) to 1 (rigid)
+    #[inline]
+    pub fn with_stiffness(mut self, stiffness: f32) -> Self {
+        self.stiffness = stiffness.clamp(0., 1.);
+        self
+    }
+
+    /// Set how far the node follows its parent each step, from 0 (stays put) to 1 (snaps)
+    #[inline]
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight.clamp(0., 1.);
+        self
+    }
+
+    /// Set the distance to this node's child, separate from how thick the node is drawn
+    #[inline]
+    pub fn with_length(mut self, length: f32) -> Self {
+        self.length = Some(length);
+        self
+    }
+
+    /// Set the rotation relative to the parent the node eases towards when relaxed.
+    /// Rotation should be in radians.
+    #[inline]
+    pub fn with_rest_rotation(mut self, rotation: f32) -> Self {
+        self.rest_rotation = rotation;
+        self
+    }
+
+    /// Set the depth used to order this node's polygons against others
+    #[inline]
+    pub fn with_z(mut self, z: f32) -> Self {
+        self.z = z;
+        self
+    }
+
+    /// Set the color used for this node's polygons
+    #[inline]
+    pub fn with_color(mut self, color: glam::Vec4) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    /// Distance this node keeps from its child
+    #[inline]
+    pub fn segment_length(&self) -> f32 {
+        self.length.unwrap_or(self.radius)
+    }
+
+    /// Create a mirror image of this node's rotation and angle limits across the given axis.
+    /// The axis is relative to the node's parent, with X being the direction the parent faces.
+    #[inline]
+    pub fn mirror(&self, axis: Axis) -> Self {
+        Self {
+            rotation: axis.mirror_angle(self.rotation),
+            max_rotation: axis.mirror_angle(self.min_rotation),
+            min_rotation: axis.mirror_angle(self.max_rotation),
+            rest_rotation: axis.mirror_angle(self.rest_rotation),
+            ..*self
+        }
+    }
+
     /// Get a point on the outer circle of the node at given angle (in radians)
     #[inline]
     pub fn get_point(&self, angle: f32) -> glam::Vec2 {
@@ -105,12 +218,56 @@ impl Node {
     }
 }
 
+/// Axis to mirror angles across.
+/// X mirrors across the facing direction (left/right), Y mirrors across the perpendicular (front/back).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+}
+
+impl Axis {
+    /// Mirror an angle (in radians) across this axis.
+    #[inline]
+    pub fn mirror_angle(&self, angle: f32) -> f32 {
+        match self {
+            Axis::X => -angle,
+            Axis::Y => PI - angle,
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct NodeID(u32);
 
+impl std::fmt::Debug for NodeID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NodeID({})", self.0)
+    }
+}
+
+impl std::fmt::Display for NodeID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 pub struct NodeManager {
     current_id: NodeID,
     nodes: HashMap<NodeID, Node>,
+    // Insertion order of the nodes, so iteration can be reproduced between runs
+    order: Vec<NodeID>,
+}
+
+/// Serializable copy of a `NodeManager` that keeps node ids intact
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NodeManagerSnapshot {
+    pub current_id: NodeID,
+    /// Nodes sorted by id
+    pub nodes: Vec<(NodeID, Node)>,
 }
 
 impl Default for NodeManager {
@@ -119,11 +276,15 @@ impl Default for NodeManager {
         Self {
             current_id: NodeID(0),
             nodes: HashMap::default(),
+            order: Vec::new(),
         }
     }
 }
 
 impl NodeManager {
+    /// Smallest radius a node can be scaled down to
+    pub const MIN_SCALED_RADIUS: f32 = 1.;
+
     #[inline]
     pub fn new() -> Self {
         Self::default()
@@ -134,6 +295,7 @@ impl NodeManager {
         let id = self.current_id;
         self.current_id.0 += 1;
         self.nodes.insert(id, node);
+        self.order.push(id);
         id
     }
 
@@ -144,163 +306,3055 @@ impl NodeManager {
             .collect::<Vec<_>>()
     }
 
+    /// Insert a chain of nodes with radii tapering linearly from `start_radius` to `end_radius`
+    pub fn insert_chain(
+        &mut self,
+        count: usize,
+        start_radius: f32,
+        end_radius: f32,
+    ) -> Vec<NodeID> {
+        let last_index = count.saturating_sub(1).max(1) as f32;
+
+        (0..count)
+            .map(|index| {
+                let t = index as f32 / last_index;
+                let radius = start_radius + (end_radius - start_radius) * t;
+                self.insert(Node::new(radius))
+            })
+            .collect()
+    }
+
+    /// Get a node, warning if the id belonged to a node that has since been removed
     #[inline]
     pub fn get_node(&self, id: &NodeID) -> Option<&Node> {
-        self.nodes.get(id)
+        let node = self.nodes.get(id);
+        if node.is_none() {
+            self.warn_if_stale(id);
+        }
+        node
     }
 
+    /// Get a node, warning if the id belonged to a node that has since been removed
     #[inline]
     pub fn get_node_mut(&mut self, id: &NodeID) -> Option<&mut Node> {
+        if !self.nodes.contains_key(id) {
+            self.warn_if_stale(id);
+        }
         self.nodes.get_mut(id)
     }
 
+    #[inline]
+    pub fn contains(&self, id: &NodeID) -> bool {
+        self.nodes.contains_key(id)
+    }
+
+    /// Check if the id was given out by this manager but its node has been removed.
+    /// Ids are never reused, so a stale id can never refer to a different node.
+    #[inline]
+    pub fn is_stale(&self, id: &NodeID) -> bool {
+        id.0 < self.current_id.0 && !self.nodes.contains_key(id)
+    }
+
+    #[inline]
+    fn warn_if_stale(&self, id: &NodeID) {
+        if self.is_stale(id) {
+            log::warn!("Accessing removed node {:?}", id);
+        }
+    }
+
+    /// All nodes in an arbitrary order. Use `iter_ordered` when the order matters.
     #[inline]
     pub fn get_values(&self) -> Values<NodeID, Node> {
         self.nodes.values()
     }
 
+    /// All nodes in the order they were inserted
+    pub fn iter_ordered(&self) -> impl Iterator<Item = (&NodeID, &Node)> {
+        self.order
+            .iter()
+            .filter_map(|id| self.nodes.get(id).map(|node| (id, node)))
+    }
+
+    /// Move all nodes from another manager into this one, giving them new IDs.
+    /// Returns a map of old IDs to new IDs so chains can be updated.
+    pub fn extend_from(&mut self, other: NodeManager) -> HashMap<NodeID, NodeID> {
+        let NodeManager {
+            mut nodes, order, ..
+        } = other;
+
+        order
+            .into_iter()
+            .filter_map(|old_id| nodes.remove(&old_id).map(|node| (old_id, node)))
+            .map(|(old_id, node)| (old_id, self.insert(node)))
+            .collect()
+    }
+
+    /// Copy the manager's nodes and next id into a form that can be saved
+    pub fn to_snapshot(&self) -> NodeManagerSnapshot {
+        let mut nodes = self
+            .nodes
+            .iter()
+            .map(|(id, node)| (*id, *node))
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|(id, _)| id.0);
+
+        NodeManagerSnapshot {
+            current_id: self.current_id,
+            nodes,
+        }
+    }
+
+    /// Rebuild a manager from a snapshot, keeping all ids the same
+    pub fn from_snapshot(snapshot: NodeManagerSnapshot) -> Self {
+        // Never reissue an id that is already in use, even if the snapshot was edited by hand
+        let next_id = snapshot
+            .nodes
+            .iter()
+            .map(|(id, _)| id.0 + 1)
+            .max()
+            .unwrap_or(0)
+            .max(snapshot.current_id.0);
+
+        Self {
+            current_id: NodeID(next_id),
+            order: snapshot.nodes.iter().map(|(id, _)| *id).collect(),
+            nodes: snapshot.nodes.into_iter().collect(),
+        }
+    }
+
+    /// Remove a node, returning it if it existed.
+    /// Ids are never reused, so other references to a removed id will simply fail to find it.
+    pub fn remove(&mut self, id: &NodeID) -> Option<Node> {
+        let Some(node) = self.nodes.remove(id) else {
+            self.warn_if_stale(id);
+            return None;
+        };
+        self.order.retain(|ordered| ordered != id);
+        Some(node)
+    }
+
+    /// Remove every node, keeping the allocation.
+    /// The id counter is not reset so old ids can never refer to new nodes.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.order.clear();
+    }
+
+    /// Remove all listed nodes, returning the ones that existed in the same order
+    pub fn remove_nodes(&mut self, ids: &[NodeID]) -> Vec<Node> {
+        let removed = ids
+            .iter()
+            .filter_map(|id| self.nodes.remove(id))
+            .collect::<Vec<_>>();
+        self.order.retain(|id| self.nodes.contains_key(id));
+        removed
+    }
+
+    /// Move every listed node by the given offset without resolving the chain.
+    /// Missing nodes are skipped.
+    pub fn translate_nodes(&mut self, ids: &[NodeID], offset: glam::Vec2) {
+        ids.iter().for_each(|id| {
+            if let Some(node) = self.nodes.get_mut(id) {
+                node.pos += offset;
+            }
+        });
+    }
+
+    /// Scale the radius of every listed node, moving them to keep proportional spacing from the first node.
+    /// Radii never go below `MIN_SCALED_RADIUS`. Missing nodes are skipped.
+    pub fn scale_nodes(&mut self, ids: &[NodeID], factor: f32) {
+        if factor.is_nan() || factor <= 0. {
+            log::warn!("Invalid scale factor {}, must be greater than 0", factor);
+            return;
+        }
+
+        let Some(root) = ids
+            .first()
+            .and_then(|id| self.nodes.get(id))
+            .map(|node| node.pos)
+        else {
+            return;
+        };
+
+        ids.iter().for_each(|id| {
+            if let Some(node) = self.nodes.get_mut(id) {
+                node.radius = (node.radius * factor).max(Self::MIN_SCALED_RADIUS);
+                node.length = node.length.map(|length| length * factor);
+                node.pos = root + (node.pos - root) * factor;
+            }
+        });
+    }
+
+    /// Find the closest node to a point within `max_dist` of it.
+    /// Nodes at the same distance are decided by the smallest id.
+    pub fn find_nearest(&self, point: glam::Vec2, max_dist: f32) -> Option<NodeID> {
+        self.nodes
+            .iter()
+            .map(|(id, node)| (*id, node.pos.distance_squared(point)))
+            .filter(|(_, distance)| *distance <= max_dist * max_dist)
+            .min_by(|(a_id, a_dist), (b_id, b_dist)| {
+                a_dist.total_cmp(b_dist).then(a_id.0.cmp(&b_id.0))
+            })
+            .map(|(id, _)| id)
+    }
+
+    /// Get the positions of the given nodes, in the same order as `ids`.
+    /// Missing nodes are skipped.
+    pub fn get_node_positions(&self, ids: &[NodeID]) -> Vec<glam::Vec2> {
+        let mut missing = Vec::new();
+
+        let positions = ids
+            .iter()
+            .filter_map(|id| match self.nodes.get(id) {
+                Some(node) => Some(node.pos),
+                None => {
+                    missing.push(id);
+                    None
+                }
+            })
+            .collect();
+
+        if !missing.is_empty() {
+            log::warn!("Some nodes do not exist: {:?}", missing);
+        }
+
+        positions
+    }
+
+    /// Save the position and rotation of the given nodes, to be put back with `restore_positions`.
+    /// Useful for undoing an edit or rejecting a bad solve.
+    pub fn snapshot_positions(&self, ids: &[NodeID]) -> Vec<(NodeID, glam::Vec2, f32)> {
+        let mut missing = Vec::new();
+
+        let snapshot = ids
+            .iter()
+            .filter_map(|id| match self.nodes.get(id) {
+                Some(node) => Some((*id, node.pos, node.rotation)),
+                None => {
+                    missing.push(id);
+                    None
+                }
+            })
+            .collect();
+
+        if !missing.is_empty() {
+            log::warn!("Some nodes do not exist: {:?}", missing);
+        }
+
+        snapshot
+    }
+
+    /// Put nodes back to the positions and rotations saved by `snapshot_positions`.
+    /// Nodes removed since the snapshot was taken are skipped.
+    pub fn restore_positions(&mut self, snapshot: &[(NodeID, glam::Vec2, f32)]) {
+        let mut missing = Vec::new();
+
+        snapshot
+            .iter()
+            .for_each(|(id, pos, rotation)| match self.nodes.get_mut(id) {
+                Some(node) => {
+                    node.pos = *pos;
+                    node.rotation = *rotation;
+                }
+                None => missing.push(id),
+            });
+
+        if !missing.is_empty() {
+            log::warn!("Some nodes do not exist: {:?}", missing);
+        }
+    }
+
+    /// Get mutable references to the given nodes, in the same order as `node_ids`.
+    /// Returns an empty Vec if any node doesn't exist or an id is repeated.
     pub fn get_nodes_mut(&mut self, node_ids: &[NodeID]) -> Vec<&mut Node> {
-        // A little verbose, this next section gets an array of mutable references to our nodes.
+        let requested = node_ids.iter().collect::<HashSet<_>>();
+
         let mut nodes = self
             .nodes
             .iter_mut()
-            .filter_map(|(id, node)| match node_ids.contains(id) {
-                true => Some((id, node)),
-                false => None,
-            })
+            .filter(|(id, _)| requested.contains(id))
             .collect::<HashMap<_, _>>();
 
-        if nodes.len() < node_ids.len() {
-            log::warn!("Invalid ik - some nodes do not exist");
+        let missing = node_ids
+            .iter()
+            .filter(|id| !nodes.contains_key(id))
+            .collect::<Vec<_>>();
+
+        if !missing.is_empty() {
+            log::warn!("Invalid ik - some nodes do not exist: {:?}", missing);
             return Vec::new();
         }
 
-        let nodes = node_ids
-            .iter()
-            .map(|id| nodes.remove(id).unwrap())
-            .collect::<Vec<_>>();
+        // Repeated ids would need multiple mutable references to the same node
+        if requested.len() != node_ids.len() {
+            log::warn!("Invalid ik - node ids are repeated: {:?}", node_ids);
+            return Vec::new();
+        }
 
-        nodes
+        node_ids.iter().filter_map(|id| nodes.remove(id)).collect()
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ForwardKinematic {
     pub nodes: Vec<NodeID>,
 }
 
-pub struct InverseKinematic {
-    pub nodes: Vec<NodeID>,
-    pub anchor: Option<glam::Vec2>,
-    pub target: glam::Vec2,
-    pub cycles: usize,
-}
-
-fn attach_node_rotations(parent: &Node, child: &mut Node) {
-    // Get Direction from parent to child
-    let direction_vector = parent.pos - child.pos;
-    child.rotation = direction_vector.to_angle();
-
-    // Get the difference in angles between parent and child and clamp if needed
-    let rotation_diff = angle_diff(child.rotation, parent.rotation);
-    let rotation_diff = rotation_diff.clamp(child.min_rotation, child.max_rotation);
-    child.rotation = parent.rotation + rotation_diff;
+impl ForwardKinematic {
+    /// First node of the chain
+    #[inline]
+    pub fn root(&self) -> Option<&NodeID> {
+        self.nodes.first()
+    }
 
-    child.pos = parent.pos - glam::Vec2::from_angle(child.rotation) * parent.radius;
-}
+    /// Last node of the chain
+    #[inline]
+    pub fn tip(&self) -> Option<&NodeID> {
+        self.nodes.last()
+    }
 
-/// Calculate difference between two angles between -π and π.
-/// Values passed in and out should be in radians.
-#[inline]
-pub fn angle_diff(a: f32, b: f32) -> f32 {
-    let a = a - b;
+    /// Node at the given index of the chain
+    #[inline]
+    pub fn nth(&self, index: usize) -> Option<&NodeID> {
+        self.nodes.get(index)
+    }
 
-    if a > PI {
-        a - TAU
-    } else if a < -PI {
-        a + TAU
-    } else {
-        a
+    /// Reverse the chain in place so the tail becomes the new root.
+    #[inline]
+    pub fn reverse(&mut self, node_manager: &mut NodeManager) {
+        reverse_chain(node_manager, &mut self.nodes);
     }
 }
 
-/// Ensure an angle is between -π and π, wrapping around if needed
-#[inline]
-pub fn _wrap_angle(angle: f32) -> f32 {
-    (angle + PI) % TAU - PI
+/// A forward kinematic chain that can branch. Each node stores the index of its parent
+/// within the tree, which must come before it so the tree can be processed in order.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForwardKinematicTree {
+    pub nodes: Vec<(NodeID, Option<usize>)>,
 }
 
-pub fn attach_node(parent: &Node, child: &mut Node) {
-    let direction_vector = parent.pos - child.pos;
-    child.rotation = direction_vector.to_angle();
+impl ForwardKinematicTree {
+    /// Add a node to the tree, returning its index for use as a parent
+    #[inline]
+    pub fn push(&mut self, id: NodeID, parent: Option<usize>) -> usize {
+        self.nodes.push((id, parent));
+        self.nodes.len() - 1
+    }
 
-    child.pos = parent.pos - glam::Vec2::from_angle(child.rotation) * parent.radius;
-}
+    /// Add a linear branch of nodes starting from the given parent
+    pub fn push_branch(&mut self, ids: &[NodeID], parent: Option<usize>) {
+        ids.iter()
+            .fold(parent, |parent, id| Some(self.push(*id, parent)));
+    }
 
-pub fn process_fk(node_manager: &mut NodeManager, fk: &ForwardKinematic) {
-    if fk.nodes.len() < 2 {
-        return;
+    /// Indices of the nodes attached directly to the given node
+    pub fn children(&self, index: usize) -> Vec<usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, parent))| *parent == Some(index))
+            .map(|(index, _)| index)
+            .collect()
     }
+}
 
-    let mut nodes = node_manager.get_nodes_mut(&fk.nodes);
+/// Where an inverse kinematic chain should reach to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TargetSource {
+    Fixed(glam::Vec2),
+    /// Follow the current position of another node
+    Node(NodeID),
+}
 
-    (1..fk.nodes.len()).for_each(|index| {
-        let (a, b) = nodes.split_at_mut(index);
+impl From<glam::Vec2> for TargetSource {
+    #[inline]
+    fn from(value: glam::Vec2) -> Self {
+        Self::Fixed(value)
+    }
+}
 
-        let parent = &a[index - 1];
-        let child = &mut b[0];
+impl TargetSource {
+    /// Get the position of the target. Returns None if the target node doesn't exist.
+    #[inline]
+    pub fn resolve(&self, node_manager: &NodeManager) -> Option<glam::Vec2> {
+        match self {
+            TargetSource::Fixed(pos) => Some(*pos),
+            TargetSource::Node(id) => node_manager.get_node(id).map(|node| node.pos),
+        }
+    }
+}
 
-        attach_node_rotations(parent, child);
-    });
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InverseKinematic {
+    pub nodes: Vec<NodeID>,
+    pub anchor: Option<glam::Vec2>,
+    pub target: TargetSource,
+    /// Weighted targets to blend between. Used instead of `target` when not empty
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub targets: Vec<(glam::Vec2, f32)>,
+    pub cycles: usize,
+    /// Distance from the target the end node must be within to count as reached
+    pub tolerance: f32,
+    /// Position that the middle joints bend towards when solving with FABRIK
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub pole: Option<glam::Vec2>,
+    /// Keep the root node's rotation fixed while solving.
+    /// When false the root swivels to face along the chain, like a shoulder.
+    #[cfg_attr(feature = "serde", serde(default = "default_anchor_locked"))]
+    pub anchor_locked: bool,
+    /// Algorithm used by `solve`
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solver: SolverKind,
+    /// Fraction of the previous frame's pose kept after a FABRIK solve, from 0 to 1.
+    /// Reduces jitter when the target sits at the edge of the chain's reach.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub smoothing: f32,
+    /// FABRIK stops early once a cycle moves every node less than this distance
+    #[cfg_attr(feature = "serde", serde(default = "default_min_progress"))]
+    pub min_progress: f32,
 }
 
-/// Forward and backward reaching inverse kinematics
-/// Returns true if the end node was able to reach the target
-pub fn fabrik(node_manager: &mut NodeManager, ik: &InverseKinematic) -> bool {
-    if ik.nodes.len() < 3 {
-        log::warn!("Invalid ik node count '{}'", ik.nodes.len());
-        return false;
-    }
+#[cfg(feature = "serde")]
+#[inline]
+fn default_anchor_locked() -> bool {
+    true
+}
 
-    let mut nodes = node_manager.get_nodes_mut(&ik.nodes);
+#[cfg(feature = "serde")]
+#[inline]
+fn default_min_progress() -> f32 {
+    InverseKinematic::DEFAULT_MIN_PROGRESS
+}
 
-    let count = nodes.len();
-    let last = nodes.len() - 1;
+impl InverseKinematic {
+    const MIN_CYCLES: usize = 10;
+    const MAX_CYCLES: usize = 40;
+    pub const DEFAULT_TOLERANCE: f32 = 5.;
+    pub const DEFAULT_MIN_PROGRESS: f32 = 0.01;
 
-    let initial_rot = nodes[0].rotation;
-    let anchor = match ik.anchor {
-        Some(anchor) => anchor,
-        None => nodes[0].pos,
-    };
+    /// Create a new chain using the recommended number of cycles for its length
+    #[inline]
+    pub fn new(
+        nodes: Vec<NodeID>,
+        anchor: Option<glam::Vec2>,
+        target: impl Into<TargetSource>,
+    ) -> Self {
+        Self {
+            cycles: Self::recommended_cycles(nodes.len()),
+            nodes,
+            anchor,
+            target: target.into(),
+            targets: Vec::new(),
+            tolerance: Self::DEFAULT_TOLERANCE,
+            pole: None,
+            anchor_locked: true,
+            solver: SolverKind::default(),
+            smoothing: 0.,
+            min_progress: Self::DEFAULT_MIN_PROGRESS,
+        }
+    }
 
-    for _ in 0..ik.cycles {
-        nodes[last].pos = ik.target;
+    /// Start building a chain with no anchor, a target at the origin and 10 cycles
+    #[inline]
+    pub fn builder(nodes: Vec<NodeID>) -> InverseKinematicBuilder {
+        InverseKinematicBuilder {
+            nodes,
+            anchor: None,
+            target: glam::Vec2::ZERO.into(),
+            cycles: Self::MIN_CYCLES,
+            tolerance: Self::DEFAULT_TOLERANCE,
+            pole: None,
+            anchor_locked: true,
+            solver: SolverKind::default(),
+            smoothing: 0.,
+            min_progress: Self::DEFAULT_MIN_PROGRESS,
+        }
+    }
 
-        (0..count - 1).rev().for_each(|index| {
-            let (a, b) = nodes.split_at_mut(index + 1);
+    /// Total length of the chain from its root to its tip
+    pub fn max_reach(&self, node_manager: &NodeManager) -> f32 {
+        let Some((_, segments)) = self.nodes.split_last() else {
+            return 0.;
+        };
 
-            let parent = &b[0];
-            let child = &mut a[index];
+        segments
+            .iter()
+            .filter_map(|id| node_manager.get_node(id))
+            .map(Node::segment_length)
+            .sum()
+    }
 
-            attach_node(parent, child);
+    /// Check if the target is close enough to the anchor (or root node if there is no anchor)
+    /// for the chain to reach it
+    pub fn is_reachable(&self, node_manager: &NodeManager) -> bool {
+        let start = self.anchor.or_else(|| {
+            self.root()
+                .and_then(|id| node_manager.get_node(id))
+                .map(|node| node.pos)
         });
 
-        nodes[0].pos = anchor;
-        nodes[0].rotation = initial_rot;
+        match (start, self.resolve_target(node_manager)) {
+            (Some(start), Some(target)) => {
+                start.distance(target) <= self.max_reach(node_manager) + self.tolerance
+            }
+            _ => false,
+        }
+    }
 
-        (1..count).for_each(|index| {
-            let (a, b) = nodes.split_at_mut(index);
+    /// Get the position the chain is solving towards.
+    /// This is the weighted average of `targets` if there are any, otherwise `target`.
+    pub fn resolve_target(&self, node_manager: &NodeManager) -> Option<glam::Vec2> {
+        if self.targets.is_empty() {
+            return self.target.resolve(node_manager);
+        }
 
-            let parent = &a[index - 1];
-            let child = &mut b[0];
+        let total_weight = self.targets.iter().map(|(_, weight)| weight).sum::<f32>();
+        if total_weight <= 0. {
+            log::warn!("Invalid ik - target weights must add up to more than 0");
+            return self.target.resolve(node_manager);
+        }
 
-            attach_node_rotations(parent, child);
-        });
+        Some(
+            self.targets
+                .iter()
+                .map(|(pos, weight)| *pos * *weight)
+                .sum::<glam::Vec2>()
+                / total_weight,
+        )
+    }
 
-        // Check if last node finished at the target
-        if (nodes[last].pos - ik.target).length() < 5. {
-            return true;
+    /// Warn that `resolve_target` failed, naming the targets it tried
+    fn warn_unresolved_target(&self) {
+        match self.targets.is_empty() {
+            true => log::warn!("Invalid ik target '{:?}'", self.target),
+            false => log::warn!(
+                "Invalid ik targets '{:?}', falling back to target '{:?}'",
+                self.targets,
+                self.target
+            ),
         }
     }
 
-    false
+    /// Set the distance from the target that counts as reached
+    #[inline]
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance.max(0.);
+        self
+    }
+
+    /// Set the position the middle joints should bend towards
+    #[inline]
+    pub fn with_pole(mut self, pole: glam::Vec2) -> Self {
+        self.pole = Some(pole);
+        self
+    }
+
+    /// Set whether the root node keeps its rotation while solving
+    #[inline]
+    pub fn with_anchor_locked(mut self, anchor_locked: bool) -> Self {
+        self.anchor_locked = anchor_locked;
+        self
+    }
+
+    /// Set the fraction of the previous frame's pose kept after solving, from 0 to 1
+    #[inline]
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing.clamp(0., 1.);
+        self
+    }
+
+    /// Set how little a FABRIK cycle can move the chain before the solve stops early
+    #[inline]
+    pub fn with_min_progress(mut self, min_progress: f32) -> Self {
+        self.min_progress = min_progress.max(0.);
+        self
+    }
+
+    /// Set the algorithm used by `solve`
+    #[inline]
+    pub fn with_solver(mut self, solver: SolverKind) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    /// Solve the chain with its chosen algorithm
+    #[inline]
+    pub fn solve(&self, node_manager: &mut NodeManager) -> SolveResult {
+        self.solver.solve(node_manager, self)
+    }
+
+    /// Suggested number of solver cycles for a chain with the given number of nodes.
+    /// Each cycle moves corrections one step further along the chain, so longer chains get one
+    /// cycle per node, kept between 10 (enough for short limbs) and 40 (to bound frame time).
+    #[inline]
+    pub fn recommended_cycles(chain_len: usize) -> usize {
+        chain_len.clamp(Self::MIN_CYCLES, Self::MAX_CYCLES)
+    }
+
+    /// First node of the chain
+    #[inline]
+    pub fn root(&self) -> Option<&NodeID> {
+        self.nodes.first()
+    }
+
+    /// Last node of the chain
+    #[inline]
+    pub fn tip(&self) -> Option<&NodeID> {
+        self.nodes.last()
+    }
+
+    /// Node at the given index of the chain
+    #[inline]
+    pub fn nth(&self, index: usize) -> Option<&NodeID> {
+        self.nodes.get(index)
+    }
+
+    /// Reverse the chain in place so the tip becomes the new base.
+    #[inline]
+    pub fn reverse(&mut self, node_manager: &mut NodeManager) {
+        reverse_chain(node_manager, &mut self.nodes);
+    }
+
+    /// Copy of this chain with the tip as the new base, for rigs authored from the other end.
+    /// The anchor and target are kept, so swap them if the chain should reach the other way.
+    /// Nodes are shared with this chain and are updated for the new order (see `reverse`),
+    /// so only the returned chain should be solved afterwards.
+    pub fn reversed(&self, node_manager: &mut NodeManager) -> InverseKinematic {
+        let mut reversed = self.clone();
+        reversed.reverse(node_manager);
+        reversed
+    }
+}
+
+/// Builder for an `InverseKinematic`, created with `InverseKinematic::builder`
+#[derive(Debug, Clone)]
+pub struct InverseKinematicBuilder {
+    nodes: Vec<NodeID>,
+    anchor: Option<glam::Vec2>,
+    target: TargetSource,
+    cycles: usize,
+    tolerance: f32,
+    pole: Option<glam::Vec2>,
+    anchor_locked: bool,
+    solver: SolverKind,
+    smoothing: f32,
+    min_progress: f32,
+}
+
+impl InverseKinematicBuilder {
+    #[inline]
+    pub fn anchor(mut self, anchor: glam::Vec2) -> Self {
+        self.anchor = Some(anchor);
+        self
+    }
+
+    #[inline]
+    pub fn target(mut self, target: impl Into<TargetSource>) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    #[inline]
+    pub fn cycles(mut self, cycles: usize) -> Self {
+        self.cycles = cycles;
+        self
+    }
+
+    #[inline]
+    pub fn tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance.max(0.);
+        self
+    }
+
+    #[inline]
+    pub fn pole(mut self, pole: glam::Vec2) -> Self {
+        self.pole = Some(pole);
+        self
+    }
+
+    #[inline]
+    pub fn anchor_locked(mut self, anchor_locked: bool) -> Self {
+        self.anchor_locked = anchor_locked;
+        self
+    }
+
+    #[inline]
+    pub fn solver(mut self, solver: SolverKind) -> Self {
+        self.solver = solver;
+        self
+    }
+
+    #[inline]
+    pub fn smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing.clamp(0., 1.);
+        self
+    }
+
+    #[inline]
+    pub fn min_progress(mut self, min_progress: f32) -> Self {
+        self.min_progress = min_progress.max(0.);
+        self
+    }
+
+    #[inline]
+    pub fn build(self) -> InverseKinematic {
+        InverseKinematic {
+            nodes: self.nodes,
+            anchor: self.anchor,
+            target: self.target,
+            targets: Vec::new(),
+            cycles: self.cycles,
+            tolerance: self.tolerance,
+            pole: self.pole,
+            anchor_locked: self.anchor_locked,
+            solver: self.solver,
+            smoothing: self.smoothing,
+            min_progress: self.min_progress,
+        }
+    }
+}
+
+/// Reverse the order of a chain and recalculate node rotations.
+/// Rotations point from a node towards its parent, so each node needs to be turned to face
+/// its new parent. The new root is turned around to face away from the rest of the chain.
+///
+/// Segment lengths belong to the parent of each segment, which changes when reversing.
+/// Each node takes the length of the node after it in the new order so every segment keeps
+/// its length, with the unused length of the old tip moving to the new tip. A length is only
+/// stored when it differs from the node's radius, so chains without explicit lengths still
+/// follow their radii afterwards (e.g. when scaled) and reversing twice restores the original
+/// lengths. Angle limits stay on their nodes, now relative to the new parent.
+fn reverse_chain(node_manager: &mut NodeManager, node_ids: &mut [NodeID]) {
+    node_ids.reverse();
+
+    let mut nodes = node_manager.get_nodes_mut(node_ids);
+    if nodes.is_empty() {
+        return;
+    }
+
+    let lengths = nodes
+        .iter()
+        .map(|node| node.segment_length())
+        .collect::<Vec<_>>();
+    let count = nodes.len();
+    nodes.iter_mut().enumerate().for_each(|(index, node)| {
+        let length = lengths[(index + 1) % count];
+        node.length = (length != node.radius).then_some(length);
+    });
+
+    nodes[0].rotation = angle_diff(nodes[0].rotation + PI, 0.);
+
+    (1..nodes.len()).for_each(|index| {
+        let (a, b) = nodes.split_at_mut(index);
+
+        let parent = &a[index - 1];
+        let child = &mut b[0];
+
+        child.rotation = (parent.pos - child.pos).to_angle();
+    });
+}
+
+fn attach_node_rotations(parent: &Node, child: &mut Node) {
+    // Get Direction from parent to child
+    let direction_vector = parent.pos - child.pos;
+    child.rotation = direction_vector.to_angle();
+
+    // Get the difference in angles between parent and child and clamp if needed
+    // Both are wrapped so the clamp can't snap to the wrong limit when crossing ±π
+    let rotation_diff = wrap_angle(angle_diff(child.rotation, parent.rotation));
+    let rotation_diff = rotation_diff.clamp(child.min_rotation, child.max_rotation);
+    let rotation_diff = rotation_diff * (1. - child.stiffness);
+    child.rotation = wrap_angle(parent.rotation + rotation_diff);
+
+    let attached_pos =
+        parent.pos - glam::Vec2::from_angle(child.rotation) * parent.segment_length();
+    child.pos = child.pos.lerp(attached_pos, child.weight);
+}
+
+/// Mirror a limb's nodes to the other side of its parent, for creating left and right pairs.
+/// Locked rotations are negated and min/max angles are swapped.
+#[inline]
+pub fn mirror_nodes(nodes: &[Node]) -> Vec<Node> {
+    nodes.iter().map(|node| node.mirror(Axis::X)).collect()
+}
+
+/// Calculate difference between two angles between -π and π.
+/// Values passed in and out should be in radians.
+#[inline]
+pub fn angle_diff(a: f32, b: f32) -> f32 {
+    let a = a - b;
+
+    if a > PI {
+        a - TAU
+    } else if a < -PI {
+        a + TAU
+    } else {
+        a
+    }
+}
+
+/// Wrap an angle into the range (-π, π].
+/// Uses `rem_euclid` so negative angles wrap the same way as positive ones.
+#[inline]
+pub fn wrap_angle(angle: f32) -> f32 {
+    PI - (PI - angle).rem_euclid(TAU)
+}
+
+pub fn attach_node(parent: &Node, child: &mut Node) {
+    attach_node_at(parent, child, parent.segment_length());
+}
+
+/// Same as `attach_node`, but with the distance between the nodes given
+#[inline]
+fn attach_node_at(parent: &Node, child: &mut Node, length: f32) {
+    let direction_vector = parent.pos - child.pos;
+    child.rotation = direction_vector.to_angle();
+
+    let attached_pos = parent.pos - glam::Vec2::from_angle(child.rotation) * length;
+    child.pos = child.pos.lerp(attached_pos, child.weight);
+}
+
+/// Ease each node in the chain towards its rest rotation by `t` (0 to 1), keeping the root in place.
+/// Useful for letting a chain curl up naturally when its target can't be reached.
+pub fn relax(node_manager: &mut NodeManager, ids: &[NodeID], t: f32) {
+    let t = t.clamp(0., 1.);
+    let mut nodes = node_manager.get_nodes_mut(ids);
+
+    (1..nodes.len()).for_each(|index| {
+        let (a, b) = nodes.split_at_mut(index);
+
+        let parent = &a[index - 1];
+        let child = &mut b[0];
+
+        let rest = parent.rotation + child.rest_rotation;
+        child.rotation += angle_diff(rest, child.rotation) * t;
+        child.pos = parent.pos - glam::Vec2::from_angle(child.rotation) * parent.segment_length();
+
+        attach_node_rotations(parent, child);
+    });
+}
+
+/// Process a branching chain, attaching each node to its parent in tree order.
+/// Parents that appear after their children are skipped with a warning.
+pub fn process_fk_tree(node_manager: &mut NodeManager, tree: &ForwardKinematicTree) {
+    tree.nodes
+        .iter()
+        .enumerate()
+        .for_each(|(index, (id, parent))| {
+            let Some(parent) = *parent else {
+                return;
+            };
+
+            let Some((parent_id, _)) = tree.nodes.get(parent).filter(|_| parent < index) else {
+                log::warn!("Invalid fk tree - node {} has parent {}", index, parent);
+                return;
+            };
+
+            let Some(parent) = node_manager.get_node(parent_id).copied() else {
+                log::warn!("Invalid fk tree - missing parent node");
+                return;
+            };
+
+            match node_manager.get_node_mut(id) {
+                Some(child) => attach_node_rotations(&parent, child),
+                None => log::warn!("Invalid fk tree - missing node"),
+            }
+        });
+}
+
+/// Propagate a forward kinematic chain from its root node.
+/// If given, `root_override` sets the position and rotation of the root node first.
+pub fn process_fk(
+    node_manager: &mut NodeManager,
+    fk: &ForwardKinematic,
+    root_override: Option<(glam::Vec2, f32)>,
+) {
+    apply_root_override(node_manager, fk, root_override);
+
+    if fk.nodes.len() < 2 {
+        return;
+    }
+
+    let mut nodes = node_manager.get_nodes_mut(&fk.nodes);
+
+    (1..fk.nodes.len()).for_each(|index| {
+        let (a, b) = nodes.split_at_mut(index);
+
+        let parent = &a[index - 1];
+        let child = &mut b[0];
+
+        attach_node_rotations(parent, child);
+    });
+}
+
+fn apply_root_override(
+    node_manager: &mut NodeManager,
+    fk: &ForwardKinematic,
+    root_override: Option<(glam::Vec2, f32)>,
+) {
+    let Some((pos, rotation)) = root_override else {
+        return;
+    };
+
+    match fk.root().and_then(|id| node_manager.get_node_mut(id)) {
+        Some(root) => {
+            root.pos = pos;
+            root.rotation = rotation;
+        }
+        None => log::warn!("Invalid fk - missing root node"),
+    }
+}
+
+/// Get the min and max corners of a box containing every node in the chain, including their radius.
+/// Returns a zero sized box at the origin if none of the nodes exist.
+pub fn chain_aabb(node_manager: &NodeManager, ids: &[NodeID]) -> (glam::Vec2, glam::Vec2) {
+    let bounds = ids
+        .iter()
+        .filter_map(|id| node_manager.get_node(id))
+        .map(|node| {
+            let radius = glam::Vec2::splat(node.radius);
+            (node.pos - radius, node.pos + radius)
+        })
+        .reduce(|(min_a, max_a), (min_b, max_b)| (min_a.min(min_b), max_a.max(max_b)));
+
+    match bounds {
+        Some(bounds) => bounds,
+        None => {
+            log::warn!("Can't get bounds of chain with no valid nodes");
+            (glam::Vec2::ZERO, glam::Vec2::ZERO)
+        }
+    }
+}
+
+/// Check if any two non-adjacent segments between consecutive node centres cross.
+/// Chains with fewer than four nodes can't fold through themselves and always return false.
+pub fn chain_self_intersects(node_manager: &NodeManager, ids: &[NodeID]) -> bool {
+    if ids.len() < 4 {
+        return false;
+    }
+
+    // Skipping a missing node would join its neighbours and could report a false crossing
+    let Some(points) = ids
+        .iter()
+        .map(|id| node_manager.get_node(id).map(|node| node.pos))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+
+    let segments = points.windows(2).collect::<Vec<_>>();
+
+    segments.iter().enumerate().any(|(index, a)| {
+        segments
+            .iter()
+            .skip(index + 2)
+            .any(|b| segments_intersect(a[0], a[1], b[0], b[1]))
+    })
+}
+
+/// Check if segment a1-a2 properly crosses segment b1-b2
+#[inline]
+fn segments_intersect(a1: glam::Vec2, a2: glam::Vec2, b1: glam::Vec2, b2: glam::Vec2) -> bool {
+    let side = |p: glam::Vec2, q: glam::Vec2, r: glam::Vec2| (q - p).perp_dot(r - p);
+
+    let d1 = side(b1, b2, a1);
+    let d2 = side(b1, b2, a2);
+    let d3 = side(a1, a2, b1);
+    let d4 = side(a1, a2, b2);
+
+    d1 * d2 < 0. && d3 * d4 < 0.
+}
+
+/// Push nodes out of any of the obstacle nodes' circles they are inside of.
+/// Each obstacle is treated as a circle of its node's radius. Nodes that are also obstacles are skipped.
+pub fn push_out_of_nodes(
+    node_manager: &mut NodeManager,
+    node_ids: &[NodeID],
+    obstacles: &[NodeID],
+) {
+    let obstacles = obstacles
+        .iter()
+        .filter_map(|id| {
+            let node = node_manager.get_node(id)?;
+            Some((*id, node.pos, node.radius))
+        })
+        .collect::<Vec<_>>();
+
+    node_ids.iter().for_each(|id| {
+        let Some(node) = node_manager.get_node_mut(id) else {
+            return;
+        };
+
+        obstacles
+            .iter()
+            .filter(|(obstacle_id, ..)| obstacle_id != id)
+            .for_each(|(_, pos, radius)| {
+                let offset = node.pos - *pos;
+                if offset.length() < *radius {
+                    node.pos = *pos + offset.normalize_or(glam::Vec2::X) * *radius;
+                }
+            });
+    });
+}
+
+/// A constant force with optional gusts that oscillate along the same direction
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Wind {
+    pub base: glam::Vec2,
+    /// Strength of gusts added on top of the base force
+    pub gust_amplitude: f32,
+    /// Gusts per second
+    pub gust_frequency: f32,
+}
+
+impl Wind {
+    /// Get the force of the wind at the given time (in seconds)
+    #[inline]
+    pub fn force(&self, elapsed: f32) -> glam::Vec2 {
+        let gust = (elapsed * self.gust_frequency * TAU).sin() * self.gust_amplitude;
+        self.base + self.base.normalize_or_zero() * gust
+    }
+
+    /// Push the given nodes by the wind at the given time
+    pub fn apply(
+        &self,
+        node_manager: &mut NodeManager,
+        node_ids: &[NodeID],
+        elapsed: f32,
+        delta_seconds: f32,
+    ) {
+        let offset = self.force(elapsed) * delta_seconds;
+        node_manager.translate_nodes(node_ids, offset);
+    }
+}
+
+/// Verlet integrated physics for a hanging chain.
+/// The first node is pinned and every other node keeps its parent's segment length away from it.
+#[derive(Debug, Clone)]
+pub struct ChainPhysics {
+    /// Number of passes used to enforce segment lengths each step
+    pub iterations: usize,
+    /// Fraction of velocity lost each step, from 0 (none) to 1 (all)
+    pub damping: f32,
+    previous: Vec<glam::Vec2>,
+}
+
+impl ChainPhysics {
+    #[inline]
+    pub fn new(iterations: usize, damping: f32) -> Self {
+        Self {
+            iterations,
+            damping: damping.clamp(0., 1.),
+            previous: Vec::new(),
+        }
+    }
+
+    /// Move the nodes by their velocity and the given acceleration, then pull them back to their segment lengths
+    pub fn step(
+        &mut self,
+        node_manager: &mut NodeManager,
+        node_ids: &[NodeID],
+        acceleration: glam::Vec2,
+        delta_seconds: f32,
+    ) {
+        let mut nodes = node_manager.get_nodes_mut(node_ids);
+        if nodes.len() < 2 {
+            return;
+        }
+
+        // Start at rest if the chain is new or has changed
+        if self.previous.len() != nodes.len() {
+            self.previous = nodes.iter().map(|node| node.pos).collect();
+        }
+
+        let step = acceleration * delta_seconds * delta_seconds;
+
+        nodes
+            .iter_mut()
+            .zip(self.previous.iter_mut())
+            .skip(1)
+            .for_each(|(node, previous)| {
+                let velocity = (node.pos - *previous) * (1. - self.damping);
+                *previous = node.pos;
+                node.pos += velocity + step;
+            });
+
+        (0..self.iterations).for_each(|_| {
+            (1..nodes.len()).for_each(|index| {
+                let (parents, children) = nodes.split_at_mut(index);
+                let parent = &mut parents[index - 1];
+                let child = &mut children[0];
+
+                let delta = child.pos - parent.pos;
+                let length = delta.length();
+                if length <= f32::EPSILON {
+                    return;
+                }
+
+                let correction = delta * ((length - parent.segment_length()) / length);
+
+                // The pinned first node can't move, so its child takes all of the correction
+                match index {
+                    1 => child.pos -= correction,
+                    _ => {
+                        parent.pos += correction * 0.5;
+                        child.pos -= correction * 0.5;
+                    }
+                }
+            });
+        });
+    }
+}
+
+/// Forward kinematics where each node springs towards its attached position instead of snapping to it,
+/// giving chains momentum and overshoot. Uses a critically damped spring so nodes settle without wobbling.
+#[derive(Debug, Clone)]
+pub struct ChainSpring {
+    /// How quickly nodes catch up to their attached position (in radians per second).
+    /// Higher values are stiffer, lower values lag further behind.
+    pub frequency: f32,
+    velocities: Vec<glam::Vec2>,
+}
+
+impl ChainSpring {
+    #[inline]
+    pub fn new(frequency: f32) -> Self {
+        Self {
+            frequency: frequency.max(0.),
+            velocities: Vec::new(),
+        }
+    }
+
+    /// Same as `process_fk`, but with each child easing towards its parent over several steps
+    pub fn process(
+        &mut self,
+        node_manager: &mut NodeManager,
+        fk: &ForwardKinematic,
+        root_override: Option<(glam::Vec2, f32)>,
+        delta_seconds: f32,
+    ) {
+        if delta_seconds <= 0. {
+            process_fk(node_manager, fk, root_override);
+            return;
+        }
+
+        apply_root_override(node_manager, fk, root_override);
+
+        let mut nodes = node_manager.get_nodes_mut(&fk.nodes);
+
+        // Start at rest if the chain is new or has changed
+        if self.velocities.len() != nodes.len() {
+            self.velocities = vec![glam::Vec2::ZERO; nodes.len()];
+        }
+
+        let frequency = self.frequency;
+
+        (1..nodes.len()).for_each(|index| {
+            let (a, b) = nodes.split_at_mut(index);
+
+            let parent = &a[index - 1];
+            let child = &mut b[0];
+            let velocity = &mut self.velocities[index];
+
+            let start = child.pos;
+
+            let mut attached = **child;
+            attach_node_rotations(parent, &mut attached);
+
+            let acceleration =
+                (attached.pos - child.pos) * frequency * frequency - *velocity * 2. * frequency;
+            *velocity += acceleration * delta_seconds;
+            child.pos += *velocity * delta_seconds;
+
+            // Keep the segment length but not the angle limits, so the node can lag behind its
+            // parent and catch up over the next few steps
+            attach_node(parent, child);
+            *velocity = (child.pos - start) / delta_seconds;
+        });
+    }
+}
+
+/// Export the positions and rotations (in degrees) of a chain as a JSON array of
+/// `{"id", "x", "y", "rotation_deg"}` objects. Missing nodes are skipped.
+pub fn export_pose_json(node_manager: &NodeManager, node_ids: &[NodeID]) -> String {
+    let entries = node_ids
+        .iter()
+        .filter_map(|id| {
+            let node = node_manager.get_node(id)?;
+            Some(format!(
+                "{{\"id\":{},\"x\":{},\"y\":{},\"rotation_deg\":{}}}",
+                id.0,
+                node.pos.x,
+                node.pos.y,
+                node.rotation.to_degrees()
+            ))
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]", entries.join(","))
+}
+
+/// Limit how far each node in a chain can rotate since the last time this was called.
+/// Nodes without a `max_angular_speed` are unaffected, but are still moved to stay attached to their parent.
+pub fn limit_angular_speed(
+    node_manager: &mut NodeManager,
+    node_ids: &[NodeID],
+    delta_seconds: f32,
+) {
+    let mut nodes = node_manager.get_nodes_mut(node_ids);
+
+    (0..nodes.len()).for_each(|index| {
+        let (a, b) = nodes.split_at_mut(index);
+        let node = &mut b[0];
+
+        if let (Some(max_speed), Some(last_rotation)) = (node.max_angular_speed, node.last_rotation)
+        {
+            let max_change = max_speed * delta_seconds;
+            let change = angle_diff(node.rotation, last_rotation).clamp(-max_change, max_change);
+            node.rotation = last_rotation + change;
+        }
+        node.last_rotation = Some(node.rotation);
+
+        if let Some(parent) = a.last() {
+            node.pos = parent.pos - glam::Vec2::from_angle(node.rotation) * parent.segment_length();
+        }
+    });
+}
+
+/// Forward and backward reaching inverse kinematics
+/// Returns true if the end node was able to reach the target
+#[inline]
+pub fn fabrik(node_manager: &mut NodeManager, ik: &InverseKinematic) -> bool {
+    solve_fabrik(node_manager, ik).reached
+}
+
+/// Solve with FABRIK using at most the shared number of `remaining` cycles, which is reduced
+/// by the number of cycles used. Unfinished chains carry on from their current pose next time.
+/// Returns true if the end node was able to reach the target
+pub fn fabrik_budgeted(
+    node_manager: &mut NodeManager,
+    ik: &InverseKinematic,
+    remaining: &mut usize,
+) -> bool {
+    let cycles = ik.cycles.min(*remaining);
+    if cycles == 0 {
+        return false;
+    }
+
+    let result = solve_fabrik_cycles(node_manager, ik, cycles);
+    *remaining -= result.iterations.min(cycles);
+    result.reached
+}
+
+/// Total number of solver cycles that can be shared between chains each frame
+#[derive(Debug, Clone, Copy)]
+pub struct SolverBudget {
+    pub total: usize,
+    remaining: usize,
+}
+
+impl SolverBudget {
+    #[inline]
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            remaining: total,
+        }
+    }
+
+    /// Refill the budget, usually at the start of each frame
+    #[inline]
+    pub fn reset(&mut self) {
+        self.remaining = self.total;
+    }
+
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.remaining
+    }
+
+    #[inline]
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// Solve the chain with FABRIK using cycles from the budget
+    #[inline]
+    pub fn fabrik(&mut self, node_manager: &mut NodeManager, ik: &InverseKinematic) -> bool {
+        fabrik_budgeted(node_manager, ik, &mut self.remaining)
+    }
+}
+
+#[inline]
+fn solve_fabrik(node_manager: &mut NodeManager, ik: &InverseKinematic) -> SolveResult {
+    solve_fabrik_cycles(node_manager, ik, ik.cycles)
+}
+
+fn solve_fabrik_cycles(
+    node_manager: &mut NodeManager,
+    ik: &InverseKinematic,
+    cycles: usize,
+) -> SolveResult {
+    if ik.nodes.len() < 3 {
+        log::warn!("Invalid ik node count '{}'", ik.nodes.len());
+        return SolveResult::default();
+    }
+
+    let Some(target) = ik.resolve_target(node_manager) else {
+        ik.warn_unresolved_target();
+        return SolveResult::default();
+    };
+
+    let mut nodes = node_manager.get_nodes_mut(&ik.nodes);
+    if nodes.is_empty() {
+        return SolveResult::default();
+    }
+
+    let count = nodes.len();
+    let last = nodes.len() - 1;
+
+    let initial_rot = nodes[0].rotation;
+    let anchor = match ik.anchor {
+        Some(anchor) => anchor,
+        None => nodes[0].pos,
+    };
+
+    let previous = nodes.iter().map(|node| node.rotation).collect::<Vec<_>>();
+    let mut result = SolveResult {
+        reached: false,
+        iterations: cycles,
+    };
+
+    for cycle in 0..cycles {
+        let cycle_start = nodes.iter().map(|node| node.pos).collect::<Vec<_>>();
+
+        if let Some(pole) = ik.pole {
+            bend_towards_pole(&mut nodes, pole);
+        }
+
+        nodes[last].pos = target;
+
+        (0..count - 1).rev().for_each(|index| {
+            let (a, b) = nodes.split_at_mut(index + 1);
+
+            let parent = &b[0];
+            let child = &mut a[index];
+
+            // Working backwards, the segment's length belongs to the child rather than the parent
+            let length = child.segment_length();
+            attach_node_at(parent, child, length);
+        });
+
+        nodes[0].pos = anchor;
+        nodes[0].rotation = root_rotation(&nodes, ik.anchor_locked, initial_rot);
+
+        (1..count).for_each(|index| {
+            let (a, b) = nodes.split_at_mut(index);
+
+            let parent = &a[index - 1];
+            let child = &mut b[0];
+
+            attach_node_rotations(parent, child);
+        });
+
+        // Check if last node finished at the target
+        if (nodes[last].pos - target).length() < ik.tolerance {
+            result = SolveResult {
+                reached: true,
+                iterations: cycle + 1,
+            };
+            break;
+        }
+
+        // Stop once the chain has settled, such as when stretched towards an unreachable target
+        let progress = nodes
+            .iter()
+            .zip(&cycle_start)
+            .map(|(node, start)| node.pos.distance(*start))
+            .fold(0., f32::max);
+
+        if progress < ik.min_progress {
+            result.iterations = cycle + 1;
+            break;
+        }
+    }
+
+    // Smoothing pulls the tip back towards its old position, so check again where it ended up
+    smooth_rotations(&mut nodes, &previous, ik.smoothing);
+    result.reached = (nodes[last].pos - target).length() < ik.tolerance;
+
+    result
+}
+
+/// Blend solved rotations back towards what they were before solving, so a chain
+/// that flips between solutions each frame eases between them instead of jittering.
+/// `smoothing` is the fraction of the previous rotation kept, from 0 (off) to 1 (frozen).
+fn smooth_rotations(nodes: &mut [&mut Node], previous: &[f32], smoothing: f32) {
+    if smoothing <= 0. {
+        return;
+    }
+
+    let t = 1. - smoothing.min(1.);
+    let blend =
+        |rotation: f32, previous: f32| wrap_angle(previous + angle_diff(rotation, previous) * t);
+
+    nodes[0].rotation = blend(nodes[0].rotation, previous[0]);
+
+    // Rebuild the chain from the root so segment lengths are kept
+    (1..nodes.len()).for_each(|index| {
+        let (a, b) = nodes.split_at_mut(index);
+
+        let parent = &a[index - 1];
+        let child = &mut b[0];
+
+        child.rotation = blend(child.rotation, previous[index]);
+        child.pos = parent.pos - glam::Vec2::from_angle(child.rotation) * parent.segment_length();
+    });
+}
+
+/// Rotation of the root node while solving. Locked roots keep their initial rotation,
+/// otherwise the root turns to face away from its child.
+#[inline]
+fn root_rotation(nodes: &[&mut Node], locked: bool, initial_rot: f32) -> f32 {
+    match locked {
+        true => initial_rot,
+        false => (nodes[0].pos - nodes[1].pos).to_angle(),
+    }
+}
+
+/// Flip any middle joint that sits on the other side of its neighbours from the pole,
+/// so the backward pass starts from a consistent bend direction.
+fn bend_towards_pole(nodes: &mut [&mut Node], pole: glam::Vec2) {
+    (1..nodes.len().saturating_sub(1)).for_each(|index| {
+        let start = nodes[index - 1].pos;
+        let line = nodes[index + 1].pos - start;
+        if line.length_squared() <= f32::EPSILON {
+            return;
+        }
+
+        let joint = nodes[index].pos;
+        if line.perp_dot(joint - start) * line.perp_dot(pole - start) >= 0. {
+            return;
+        }
+
+        // Reflect the joint across the line between its neighbours
+        let projected = start + line * ((joint - start).dot(line) / line.length_squared());
+        nodes[index].pos = projected * 2. - joint;
+    });
+}
+
+/// Cyclic coordinate descent inverse kinematics
+/// Returns true if the end node was able to reach the target
+#[inline]
+pub fn ccd(node_manager: &mut NodeManager, ik: &InverseKinematic) -> bool {
+    solve_ccd(node_manager, ik).reached
+}
+
+fn solve_ccd(node_manager: &mut NodeManager, ik: &InverseKinematic) -> SolveResult {
+    if ik.nodes.len() < 2 {
+        log::warn!("Invalid ik node count '{}'", ik.nodes.len());
+        return SolveResult::default();
+    }
+
+    let Some(target) = ik.resolve_target(node_manager) else {
+        ik.warn_unresolved_target();
+        return SolveResult::default();
+    };
+
+    let mut nodes = node_manager.get_nodes_mut(&ik.nodes);
+    if nodes.is_empty() {
+        return SolveResult::default();
+    }
+
+    let count = nodes.len();
+    let last = nodes.len() - 1;
+
+    let initial_rot = nodes[0].rotation;
+    if let Some(anchor) = ik.anchor {
+        nodes[0].pos = anchor;
+    }
+
+    for cycle in 0..ik.cycles {
+        // Rotate everything after each joint so the end node points at the target
+        (0..last).rev().for_each(|joint| {
+            let pivot = nodes[joint].pos;
+
+            let to_end = nodes[last].pos - pivot;
+            let to_target = target - pivot;
+            if to_end.length_squared() < f32::EPSILON || to_target.length_squared() < f32::EPSILON {
+                return;
+            }
+
+            let angle = angle_diff(to_target.to_angle(), to_end.to_angle());
+            let rotation = glam::Vec2::from_angle(angle);
+
+            nodes[joint + 1..].iter_mut().for_each(|node| {
+                node.pos = pivot + rotation.rotate(node.pos - pivot);
+                node.rotation += angle;
+            });
+        });
+
+        // Reapply joint limits and segment lengths
+        nodes[0].rotation = root_rotation(&nodes, ik.anchor_locked, initial_rot);
+
+        (1..count).for_each(|index| {
+            let (a, b) = nodes.split_at_mut(index);
+
+            let parent = &a[index - 1];
+            let child = &mut b[0];
+
+            attach_node_rotations(parent, child);
+        });
+
+        if (nodes[last].pos - target).length() < ik.tolerance {
+            return SolveResult {
+                reached: true,
+                iterations: cycle + 1,
+            };
+        }
+    }
+
+    SolveResult {
+        reached: false,
+        iterations: ik.cycles,
+    }
+}
+
+/// Outcome of an inverse kinematic solve
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SolveResult {
+    /// True if the end node was able to reach the target
+    pub reached: bool,
+    /// Number of cycles the solver ran
+    pub iterations: usize,
+}
+
+/// Common interface for inverse kinematic algorithms so they can be swapped per chain
+pub trait IkSolver {
+    fn solve(&self, node_manager: &mut NodeManager, ik: &InverseKinematic) -> SolveResult;
+
+    /// Short name of the algorithm, used for diagnostics
+    fn name(&self) -> &'static str;
+}
+
+/// Forward and backward reaching inverse kinematics
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Fabrik;
+
+impl IkSolver for Fabrik {
+    #[inline]
+    fn solve(&self, node_manager: &mut NodeManager, ik: &InverseKinematic) -> SolveResult {
+        solve_fabrik(node_manager, ik)
+    }
+
+    #[inline]
+    fn name(&self) -> &'static str {
+        "FABRIK"
+    }
+}
+
+/// Cyclic coordinate descent
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Ccd;
+
+impl IkSolver for Ccd {
+    #[inline]
+    fn solve(&self, node_manager: &mut NodeManager, ik: &InverseKinematic) -> SolveResult {
+        solve_ccd(node_manager, ik)
+    }
+
+    #[inline]
+    fn name(&self) -> &'static str {
+        "CCD"
+    }
+}
+
+/// Built in solver chosen per chain, used by `InverseKinematic::solve`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SolverKind {
+    #[default]
+    Fabrik,
+    Ccd,
+}
+
+impl IkSolver for SolverKind {
+    #[inline]
+    fn solve(&self, node_manager: &mut NodeManager, ik: &InverseKinematic) -> SolveResult {
+        match self {
+            SolverKind::Fabrik => solve_fabrik(node_manager, ik),
+            SolverKind::Ccd => solve_ccd(node_manager, ik),
+        }
+    }
+
+    #[inline]
+    fn name(&self) -> &'static str {
+        match self {
+            SolverKind::Fabrik => Fabrik.name(),
+            SolverKind::Ccd => Ccd.name(),
+        }
+    }
+}
+
+/// Status of a single chain at the end of a frame
+#[derive(Debug, Clone, Copy)]
+pub struct ChainReport {
+    pub name: &'static str,
+    pub length: usize,
+    /// Solver used by the chain, None for forward kinematic chains
+    pub solver: Option<&'static str>,
+    /// Result of the last solve, None for forward kinematic chains
+    pub result: Option<SolveResult>,
+}
+
+/// Collects the status of every active chain each frame for diagnostics
+#[derive(Debug, Default)]
+pub struct ChainRegistry {
+    reports: Vec<ChainReport>,
+}
+
+impl ChainRegistry {
+    #[inline]
+    pub fn clear(&mut self) {
+        self.reports.clear();
+    }
+
+    #[inline]
+    pub fn register_fk(&mut self, name: &'static str, fk: &ForwardKinematic) {
+        self.reports.push(ChainReport {
+            name,
+            length: fk.nodes.len(),
+            solver: None,
+            result: None,
+        });
+    }
+
+    #[inline]
+    pub fn register_ik(&mut self, name: &'static str, ik: &InverseKinematic, result: SolveResult) {
+        self.reports.push(ChainReport {
+            name,
+            length: ik.nodes.len(),
+            solver: Some(ik.solver.name()),
+            result: Some(result),
+        });
+    }
+
+    #[inline]
+    pub fn reports(&self) -> &[ChainReport] {
+        &self.reports
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.reports.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.reports.is_empty()
+    }
+
+    /// Number of inverse kinematic chains that failed to reach their target
+    #[inline]
+    pub fn unreached_count(&self) -> usize {
+        self.reports
+            .iter()
+            .filter(|report| matches!(report.result, Some(SolveResult { reached: false, .. })))
+            .count()
+    }
+
+    /// One line per chain, listing its length, solver and last result
+    pub fn summary(&self) -> String {
+        self.reports
+            .iter()
+            .map(|report| match (report.solver, report.result) {
+                (Some(solver), Some(result)) => format!(
+                    "{}: {} nodes, {} in {} iterations, {}",
+                    report.name,
+                    report.length,
+                    solver,
+                    result.iterations,
+                    if result.reached {
+                        "reached"
+                    } else {
+                        "unreached"
+                    }
+                ),
+                _ => format!("{}: {} nodes, FK", report.name, report.length),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reversed_fk_chain_mirrors_original() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 5, 20.);
+        nodes.iter().enumerate().for_each(|(index, id)| {
+            node_manager.get_node_mut(id).unwrap().radius = 10. + index as f32 * 5.;
+        });
+
+        let mut fk = ForwardKinematic { nodes };
+        process_fk(&mut node_manager, &fk, None);
+        let positions = node_manager.get_node_positions(&fk.nodes);
+        let rotations = fk
+            .nodes
+            .iter()
+            .map(|id| node_manager.get_node(id).unwrap().rotation)
+            .collect::<Vec<_>>();
+
+        fk.reverse(&mut node_manager);
+        process_fk(&mut node_manager, &fk, None);
+
+        // Same shape, walked from the other end with every node facing the opposite way
+        let reversed = node_manager.get_node_positions(&fk.nodes);
+        reversed.iter().rev().zip(&positions).for_each(|(a, b)| {
+            assert!(a.distance(*b) < 1e-3, "{} moved to {}", b, a);
+        });
+
+        let count = rotations.len();
+        fk.nodes.iter().enumerate().for_each(|(index, id)| {
+            let rotation = node_manager.get_node(id).unwrap().rotation;
+            let original = rotations[(count - index).min(count - 1)];
+            assert!(
+                angle_diff(rotation, original + PI).abs() < 1e-3,
+                "node {} has rotation {}, expected {}",
+                index,
+                rotation,
+                original + PI
+            );
+        });
+    }
+
+    #[test]
+    fn mirrored_nodes_negate_angles() {
+        // A mirrored limb reaches at the negated angle
+        assert_eq!(Axis::X.mirror_angle(1.2), -1.2);
+
+        // Limits swap so the mirrored range covers the negated original range
+        let node = Node::angles(20., -0.8, 0.3).mirror(Axis::X);
+        assert_eq!(node.min_rotation, -0.3);
+        assert_eq!(node.max_rotation, 0.8);
+
+        let node = Node::locked(15., 0.4).mirror(Axis::X);
+        assert_eq!(node.min_rotation, -0.4);
+        assert_eq!(node.max_rotation, -0.4);
+    }
+
+    #[test]
+    fn node_id_debug_shows_index() {
+        let mut node_manager = NodeManager::new();
+        let ids = node_manager.insert_nodes(&[Node::default(); 13]);
+
+        assert_eq!(format!("{:?}", ids[12]), format!("NodeID({})", ids[12].0));
+        assert!(format!("{:?}", ids[12]).contains(&ids[12].to_string()));
+    }
+
+    #[test]
+    fn translating_solved_chain_keeps_its_shape() {
+        let mut node_manager = NodeManager::new();
+        let nodes = node_manager.insert_nodes(&[Node::unlocked(20.); 5]);
+        let ik =
+            InverseKinematic::new(nodes.clone(), Some(glam::Vec2::ZERO), glam::vec2(-60., 30.));
+        fabrik(&mut node_manager, &ik);
+        let positions = |node_manager: &NodeManager| {
+            nodes
+                .iter()
+                .map(|id| node_manager.get_node(id).unwrap().pos)
+                .collect::<Vec<_>>()
+        };
+        let before = positions(&node_manager);
+
+        // Missing ids are skipped
+        let missing = NodeID(nodes.len() as u32 + 1);
+        let offset = glam::vec2(120., -45.);
+        node_manager.translate_nodes(&[nodes.as_slice(), &[missing]].concat(), offset);
+
+        let after = positions(&node_manager);
+        before.iter().zip(&after).for_each(|(before, after)| {
+            assert!(after.distance(*before + offset) < 1e-4);
+        });
+        before
+            .windows(2)
+            .zip(after.windows(2))
+            .for_each(|(before, after)| {
+                assert!((before[0].distance(before[1]) - after[0].distance(after[1])).abs() < 1e-4);
+            });
+    }
+
+    /// A chain with a gentle curl, so solving doesn't start from a straight line
+    fn curled_chain(node_manager: &mut NodeManager, count: usize, radius: f32) -> Vec<NodeID> {
+        let nodes = node_manager.insert_nodes(&vec![Node::unlocked(radius); count]);
+
+        let mut pos = glam::Vec2::ZERO;
+        nodes.iter().enumerate().for_each(|(index, id)| {
+            let node = node_manager.get_node_mut(id).unwrap();
+            node.pos = pos;
+            pos -= glam::Vec2::from_angle(index as f32 * 0.2) * node.segment_length();
+        });
+
+        nodes
+    }
+
+    #[test]
+    fn node_target_follows_moving_node() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 6, 20.);
+        let tip = *nodes.last().unwrap();
+        let target = node_manager.insert(Node {
+            pos: glam::vec2(-60., 40.),
+            ..Node::default()
+        });
+
+        let ik = InverseKinematic {
+            cycles: 40,
+            ..InverseKinematic::new(nodes, Some(glam::Vec2::ZERO), TargetSource::Node(target))
+        };
+        assert!(fabrik(&mut node_manager, &ik));
+
+        // Solving again after the target node moves follows it to its new position
+        let moved = glam::vec2(-70., -30.);
+        node_manager.get_node_mut(&target).unwrap().pos = moved;
+        assert!(fabrik(&mut node_manager, &ik));
+
+        let tip = node_manager.get_node(&tip).unwrap().pos;
+        assert!(tip.distance(moved) < 5., "tip {} target {}", tip, moved);
+    }
+
+    #[test]
+    fn every_solver_reaches_reachable_target() {
+        let solvers: [(&str, &dyn IkSolver); 2] = [("fabrik", &Fabrik), ("ccd", &Ccd)];
+
+        solvers.into_iter().for_each(|(name, solver)| {
+            let mut node_manager = NodeManager::new();
+            let nodes = curled_chain(&mut node_manager, 6, 20.);
+            let tip = *nodes.last().unwrap();
+
+            let target = glam::vec2(-60., 40.);
+            let ik = InverseKinematic {
+                cycles: 40,
+                ..InverseKinematic::new(nodes, Some(glam::Vec2::ZERO), target)
+            };
+
+            let result = solver.solve(&mut node_manager, &ik);
+            let tip = node_manager.get_node(&tip).unwrap().pos;
+            assert!(result.reached, "{} didn't reach the target", name);
+            assert!(tip.distance(target) < 5., "{} tip {}", name, tip);
+        });
+    }
+
+    #[test]
+    fn chain_indexing_is_bounds_checked() {
+        let mut node_manager = NodeManager::new();
+        let nodes = node_manager.insert_nodes(&[Node::default(); 3]);
+
+        let fk = ForwardKinematic {
+            nodes: nodes.clone(),
+        };
+        assert_eq!(fk.root(), Some(&nodes[0]));
+        assert_eq!(fk.tip(), Some(&nodes[2]));
+        assert_eq!(fk.nth(1), Some(&nodes[1]));
+        assert_eq!(fk.nth(3), None);
+
+        let ik = InverseKinematic::new(nodes.clone(), None, glam::Vec2::ZERO);
+        assert_eq!(ik.tip(), Some(&nodes[2]));
+        assert_eq!(ik.nth(usize::MAX), None);
+
+        let empty = ForwardKinematic { nodes: Vec::new() };
+        assert_eq!(empty.root(), None);
+        assert_eq!(empty.tip(), None);
+        assert_eq!(empty.nth(0), None);
+    }
+
+    #[test]
+    fn root_override_places_root_and_drives_chain() {
+        let mut node_manager = NodeManager::new();
+        let fk = ForwardKinematic {
+            nodes: node_manager.insert_nodes(&[Node::new(20.); 4]),
+        };
+
+        let (pos, rotation) = (glam::vec2(50., -20.), 0.5);
+        process_fk(&mut node_manager, &fk, Some((pos, rotation)));
+
+        let root = node_manager.get_node(&fk.nodes[0]).unwrap();
+        assert_eq!(root.pos, pos);
+        assert_eq!(root.rotation, rotation);
+
+        // Each node hangs off the one before it, starting from the moved root
+        fk.nodes.windows(2).for_each(|pair| {
+            let parent = node_manager.get_node(&pair[0]).unwrap();
+            let child = node_manager.get_node(&pair[1]).unwrap();
+
+            let expected = parent.pos - glam::Vec2::from_angle(child.rotation) * 20.;
+            assert!(
+                child.pos.distance(expected) < 1e-3,
+                "{} != {}",
+                child.pos,
+                expected
+            );
+
+            let bend = angle_diff(child.rotation, parent.rotation);
+            assert!(bend.abs() <= Node::DEFAULT_ANGLE + 1e-5);
+        });
+    }
+
+    #[test]
+    fn angular_speed_limits_rotation_per_step() {
+        let mut node_manager = NodeManager::new();
+        let node = Node {
+            rotation: 1.,
+            max_angular_speed: Some(2.),
+            ..Node::unlocked(20.)
+        };
+        let nodes = node_manager.insert_nodes(&[node; 2]);
+
+        // The first limit only records where the nodes start, rather than swinging them in from 0
+        limit_angular_speed(&mut node_manager, &nodes, 0.1);
+        nodes.iter().for_each(|id| {
+            assert_eq!(node_manager.get_node(id).unwrap().rotation, 1.);
+        });
+
+        // Asked to turn half a circle in one step, only max_angular_speed * dt happens
+        nodes.iter().for_each(|id| {
+            node_manager.get_node_mut(id).unwrap().rotation = 1. + PI;
+        });
+        limit_angular_speed(&mut node_manager, &nodes, 0.1);
+
+        nodes.iter().for_each(|id| {
+            let rotation = node_manager.get_node(id).unwrap().rotation;
+            assert!(
+                (angle_diff(rotation, 1.).abs() - 0.2).abs() < 1e-5,
+                "rotated to {}",
+                rotation
+            );
+        });
+
+        let parent = node_manager.get_node(&nodes[0]).unwrap();
+        let child = node_manager.get_node(&nodes[1]).unwrap();
+        assert!((parent.pos.distance(child.pos) - parent.radius).abs() < 1e-3);
+    }
+
+    #[test]
+    fn pose_json_lists_each_node() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 3, 20.);
+        node_manager.get_node_mut(&nodes[1]).unwrap().rotation = PI / 2.;
+
+        let json = export_pose_json(&node_manager, &nodes);
+        let pose: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let entries = pose.as_array().unwrap();
+        assert_eq!(entries.len(), 3);
+
+        entries.iter().zip(&nodes).for_each(|(entry, id)| {
+            let node = node_manager.get_node(id).unwrap();
+            assert_eq!(entry["id"].as_u64(), Some(id.0 as u64));
+            assert_eq!(entry["x"].as_f64().map(|x| x as f32), Some(node.pos.x));
+            assert_eq!(entry["y"].as_f64().map(|y| y as f32), Some(node.pos.y));
+            assert!(entry["rotation_deg"].is_number());
+        });
+        assert!((entries[1]["rotation_deg"].as_f64().unwrap() - 90.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn stiff_nodes_bend_less() {
+        let bend = |stiffness: f32| {
+            let mut node_manager = NodeManager::new();
+            let parent = node_manager.insert(Node::unlocked(20.));
+            let child = node_manager.insert(Node {
+                pos: -glam::Vec2::from_angle(0.5) * 20.,
+                stiffness,
+                ..Node::unlocked(20.)
+            });
+
+            let fk = ForwardKinematic {
+                nodes: vec![parent, child],
+            };
+            process_fk(&mut node_manager, &fk, None);
+
+            angle_diff(node_manager.get_node(&child).unwrap().rotation, 0.)
+        };
+
+        assert!((bend(0.) - 0.5).abs() < 1e-4);
+        assert!(bend(0.8).abs() < bend(0.).abs());
+        assert!((bend(0.8) - 0.1).abs() < 1e-4);
+        assert_eq!(bend(1.), 0.);
+    }
+
+    #[test]
+    fn extend_from_maps_every_merged_node() {
+        let mut node_manager = NodeManager::new();
+        let ours = node_manager.insert_nodes(&[Node::new(10.); 3]);
+
+        let mut other = NodeManager::new();
+        let theirs = (0..3)
+            .map(|index| other.insert(Node::new(20. + index as f32)))
+            .collect::<Vec<_>>();
+
+        let map = node_manager.extend_from(other);
+        assert_eq!(node_manager.get_values().count(), 6);
+        assert_eq!(map.len(), 3);
+
+        theirs.iter().enumerate().for_each(|(index, old_id)| {
+            let new_id = map[old_id];
+            assert!(!ours.contains(&new_id));
+            assert_eq!(
+                node_manager.get_node(&new_id).unwrap().radius,
+                20. + index as f32
+            );
+        });
+    }
+
+    #[test]
+    fn wind_gusts_oscillate_at_frequency() {
+        let calm = Wind {
+            base: glam::vec2(40., 0.),
+            gust_amplitude: 0.,
+            gust_frequency: 0.5,
+        };
+        (0..20).for_each(|step| assert_eq!(calm.force(step as f32 * 0.13), calm.base));
+
+        // Gusts peak a quarter of the way through each period and repeat every period
+        let gusty = Wind {
+            gust_amplitude: 10.,
+            ..calm
+        };
+        let period = 1. / gusty.gust_frequency;
+        assert!(gusty.force(period * 0.25).distance(glam::vec2(50., 0.)) < 1e-4);
+        assert!(gusty.force(period * 0.75).distance(glam::vec2(30., 0.)) < 1e-4);
+        (0..20).for_each(|step| {
+            let elapsed = step as f32 * 0.13;
+            assert!(gusty.force(elapsed).distance(gusty.force(elapsed + period)) < 1e-3);
+        });
+    }
+
+    #[test]
+    fn recommended_cycles_grow_with_length_up_to_max() {
+        let short = InverseKinematic::recommended_cycles(4);
+        let long = InverseKinematic::recommended_cycles(30);
+        assert!(long > short);
+
+        assert_eq!(
+            InverseKinematic::recommended_cycles(100_000),
+            InverseKinematic::MAX_CYCLES
+        );
+        assert_eq!(
+            InverseKinematic::recommended_cycles(0),
+            InverseKinematic::MIN_CYCLES
+        );
+    }
+
+    #[test]
+    fn limb_nodes_are_pushed_to_body_surface() {
+        let mut node_manager = NodeManager::new();
+        let body = node_manager.insert(Node {
+            pos: glam::vec2(100., 50.),
+            ..Node::new(30.)
+        });
+        let inside = node_manager.insert(Node {
+            pos: glam::vec2(110., 50.),
+            ..Node::new(5.)
+        });
+        let outside = node_manager.insert(Node {
+            pos: glam::vec2(100., 10.),
+            ..Node::new(5.)
+        });
+
+        push_out_of_nodes(&mut node_manager, &[body, inside, outside], &[body]);
+
+        // Pushed straight out along the offset from the body node
+        let pushed = node_manager.get_node(&inside).unwrap().pos;
+        assert!(pushed.distance(glam::vec2(130., 50.)) < 1e-4, "{}", pushed);
+
+        assert_eq!(
+            node_manager.get_node(&outside).unwrap().pos,
+            glam::vec2(100., 10.)
+        );
+        assert_eq!(
+            node_manager.get_node(&body).unwrap().pos,
+            glam::vec2(100., 50.)
+        );
+    }
+
+    #[test]
+    fn scaling_doubles_radii_and_spacing() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 5, 20.);
+        node_manager.translate_nodes(&nodes, glam::vec2(40., 25.));
+        let positions = |node_manager: &NodeManager| {
+            nodes
+                .iter()
+                .map(|id| node_manager.get_node(id).unwrap().pos)
+                .collect::<Vec<_>>()
+        };
+        let before = positions(&node_manager);
+
+        node_manager.scale_nodes(&nodes, 2.);
+        let after = positions(&node_manager);
+
+        assert_eq!(after[0], before[0]);
+        nodes.iter().for_each(|id| {
+            assert_eq!(node_manager.get_node(id).unwrap().radius, 40.);
+        });
+        before
+            .windows(2)
+            .zip(after.windows(2))
+            .for_each(|(before, after)| {
+                let expected = before[0].distance(before[1]) * 2.;
+                assert!((after[0].distance(after[1]) - expected).abs() < 1e-3);
+            });
+    }
+
+    #[test]
+    fn registry_reports_chains_and_status() {
+        let mut node_manager = NodeManager::new();
+        let fk = ForwardKinematic {
+            nodes: node_manager.insert_nodes(&[Node::default(); 4]),
+        };
+        let nodes = node_manager.insert_nodes(&[Node::default(); 3]);
+        let arm = InverseKinematic::builder(nodes.clone()).build();
+        let leg = InverseKinematic::builder(nodes)
+            .solver(SolverKind::Ccd)
+            .build();
+
+        let mut registry = ChainRegistry::default();
+        registry.register_fk("body", &fk);
+        registry.register_ik(
+            "arm",
+            &arm,
+            SolveResult {
+                reached: true,
+                iterations: 3,
+            },
+        );
+        registry.register_ik("leg", &leg, SolveResult::default());
+
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.unreached_count(), 1);
+
+        let reports = registry.reports();
+        assert_eq!(reports[0].length, 4);
+        assert_eq!(reports[0].solver, None);
+        assert_eq!(reports[1].solver, Some("FABRIK"));
+        assert_eq!(reports[2].solver, Some("CCD"));
+        assert_eq!(registry.summary().lines().count(), 3);
+
+        registry.clear();
+        assert!(registry.is_empty());
+        assert_eq!(registry.unreached_count(), 0);
+    }
+
+    #[test]
+    fn get_nodes_mut_follows_requested_order() {
+        let mut node_manager = NodeManager::new();
+        let ids = (0..5)
+            .map(|index| node_manager.insert(Node::new(index as f32)))
+            .collect::<Vec<_>>();
+
+        let requested = [ids[3], ids[0], ids[4]];
+        let radii = node_manager
+            .get_nodes_mut(&requested)
+            .iter()
+            .map(|node| node.radius)
+            .collect::<Vec<_>>();
+        assert_eq!(radii, [3., 0., 4.]);
+
+        // Repeated or missing ids give nothing rather than aliasing or partial chains
+        assert!(node_manager.get_nodes_mut(&[ids[1], ids[1]]).is_empty());
+        let missing = NodeID(ids.len() as u32 + 1);
+        assert!(node_manager.get_nodes_mut(&[ids[1], missing]).is_empty());
+    }
+
+    #[test]
+    fn removing_nodes_leaves_the_rest() {
+        let mut node_manager = NodeManager::new();
+        let ids = node_manager.insert_nodes(&[Node::default(); 10]);
+
+        let removed = node_manager.remove_nodes(&[ids[1], ids[4], ids[7]]);
+        assert_eq!(removed.len(), 3);
+        assert_eq!(node_manager.get_values().count(), 7);
+
+        [ids[1], ids[4], ids[7]].iter().for_each(|id| {
+            assert!(node_manager.get_node(id).is_none());
+        });
+        assert!(node_manager.get_node(&ids[0]).is_some());
+    }
+
+    #[test]
+    fn half_weight_nodes_move_half_as_far() {
+        let parent = Node {
+            pos: glam::vec2(100., 0.),
+            ..Node::new(20.)
+        };
+        let moved = |weight: f32| {
+            let mut child = Node::new(20.).with_weight(weight);
+            attach_node(&parent, &mut child);
+            child.pos.length()
+        };
+
+        // A full weight node snaps to 20 units from its parent, 80 from where it started
+        assert!((moved(1.) - 80.).abs() < 1e-4);
+        assert!((moved(0.5) - moved(1.) * 0.5).abs() < 1e-4);
+        assert_eq!(moved(0.), 0.);
+    }
+
+    #[test]
+    fn looser_tolerance_stops_sooner() {
+        let iterations = |tolerance: f32| {
+            let mut node_manager = NodeManager::new();
+            let nodes = curled_chain(&mut node_manager, 8, 20.);
+            let ik = InverseKinematic {
+                cycles: 40,
+                ..InverseKinematic::new(nodes, Some(glam::Vec2::ZERO), glam::vec2(-90., 60.))
+            }
+            .with_tolerance(tolerance);
+
+            let result = Fabrik.solve(&mut node_manager, &ik);
+            assert!(result.reached);
+            result.iterations
+        };
+
+        assert!(iterations(10.) < iterations(0.1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn rig_round_trips_through_json() {
+        let mut node_manager = NodeManager::new();
+        let body = ForwardKinematic {
+            nodes: curled_chain(&mut node_manager, 6, 20.),
+        };
+        let mut limb_nodes = vec![body.nodes[2]];
+        limb_nodes.extend(node_manager.insert_nodes(&[Node::unlocked(15.); 4]));
+        let limbs = vec![InverseKinematic::new(
+            limb_nodes,
+            None,
+            glam::vec2(40., -30.),
+        )];
+        process_fk(&mut node_manager, &body, Some((glam::vec2(30., -10.), 0.7)));
+
+        let json = serde_json::to_string(&(node_manager.to_snapshot(), &body, &limbs)).unwrap();
+        let (snapshot, loaded_body, loaded_limbs): (
+            NodeManagerSnapshot,
+            ForwardKinematic,
+            Vec<InverseKinematic>,
+        ) = serde_json::from_str(&json).unwrap();
+        let loaded = NodeManager::from_snapshot(snapshot);
+
+        assert_eq!(loaded_body.nodes, body.nodes);
+        assert_eq!(loaded_limbs.len(), limbs.len());
+        body.nodes
+            .iter()
+            .chain(limbs.iter().flat_map(|limb| &limb.nodes))
+            .for_each(|id| {
+                let original = node_manager.get_node(id).unwrap();
+                let loaded = loaded.get_node(id).unwrap();
+                assert_eq!(loaded.pos, original.pos);
+                assert_eq!(loaded.rotation, original.rotation);
+            });
+    }
+
+    #[test]
+    fn folded_chain_self_intersects() {
+        let mut node_manager = NodeManager::new();
+
+        let mut chain = |points: [glam::Vec2; 5]| {
+            points.map(|pos| {
+                node_manager.insert(Node {
+                    pos,
+                    ..Node::new(1.)
+                })
+            })
+        };
+
+        let straight = chain([0., 10., 20., 30., 40.].map(|x| glam::vec2(x, 0.)));
+        // Folds back over the first segment
+        let folded = chain([
+            glam::vec2(0., 0.),
+            glam::vec2(10., 0.),
+            glam::vec2(10., 10.),
+            glam::vec2(5., 10.),
+            glam::vec2(5., -5.),
+        ]);
+
+        assert!(!chain_self_intersects(&node_manager, &straight));
+        assert!(chain_self_intersects(&node_manager, &folded));
+    }
+
+    #[test]
+    fn node_positions_follow_id_order() {
+        let mut node_manager = NodeManager::new();
+        let ids = (0..4)
+            .map(|index| {
+                node_manager.insert(Node {
+                    pos: glam::vec2(index as f32, -(index as f32)),
+                    ..Node::new(10.)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let order = [ids[2], ids[0], ids[3], ids[1]];
+        assert_eq!(
+            node_manager.get_node_positions(&order),
+            vec![
+                glam::vec2(2., -2.),
+                glam::vec2(0., 0.),
+                glam::vec2(3., -3.),
+                glam::vec2(1., -1.),
+            ]
+        );
+    }
+
+    #[test]
+    fn self_intersection_ignores_chains_with_missing_nodes() {
+        let mut node_manager = NodeManager::new();
+
+        // A hook that only crosses itself if the second node is skipped
+        let ids = [
+            glam::vec2(0., 0.),
+            glam::vec2(0., 10.),
+            glam::vec2(10., 10.),
+            glam::vec2(10., 0.),
+            glam::vec2(3., 5.),
+        ]
+        .map(|pos| {
+            node_manager.insert(Node {
+                pos,
+                ..Node::new(1.)
+            })
+        });
+        assert!(!chain_self_intersects(&node_manager, &ids));
+
+        node_manager.remove(&ids[1]);
+        assert!(!chain_self_intersects(&node_manager, &ids));
+    }
+
+    #[test]
+    fn clamped_rotation_is_continuous_across_pi() {
+        let parent = Node {
+            rotation: 3.,
+            ..Node::angle(10., 0.5)
+        };
+
+        // Child sits in the direction of -3 rad from the parent, just across the ±π seam
+        let mut child = Node {
+            pos: parent.pos - glam::Vec2::from_angle(-3.) * parent.radius,
+            ..Node::angle(10., 0.5)
+        };
+        attach_node_rotations(&parent, &mut child);
+
+        // -3 is only ~0.28 rad from 3 going the short way, so it's inside the limits
+        let diff = angle_diff(child.rotation, parent.rotation);
+        assert!(diff.abs() < 0.5, "child rotation {} jumped", child.rotation);
+        assert!(angle_diff(child.rotation, -3.).abs() < 1e-4);
+
+        // Further round it's clamped to the near limit instead of snapping to the far one
+        child.pos = parent.pos - glam::Vec2::from_angle(-2.) * parent.radius;
+        attach_node_rotations(&parent, &mut child);
+
+        let diff = angle_diff(child.rotation, parent.rotation);
+        assert!(
+            (diff - 0.5).abs() < 1e-4,
+            "child rotation {} jumped",
+            child.rotation
+        );
+    }
+
+    #[test]
+    fn equal_weight_targets_leave_tip_equidistant() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 6, 20.);
+        let tip = *nodes.last().unwrap();
+
+        let (a, b) = (glam::vec2(-80., 40.), glam::vec2(-60., -20.));
+        let ik = InverseKinematic {
+            targets: vec![(a, 1.), (b, 1.)],
+            cycles: 40,
+            ..InverseKinematic::new(nodes, Some(glam::Vec2::ZERO), glam::Vec2::ZERO)
+        }
+        .with_tolerance(0.01);
+
+        assert!(fabrik(&mut node_manager, &ik));
+        let tip = node_manager.get_node(&tip).unwrap().pos;
+        assert!(
+            (tip.distance(a) - tip.distance(b)).abs() < 0.02,
+            "tip {} is {} from a and {} from b",
+            tip,
+            tip.distance(a),
+            tip.distance(b)
+        );
+    }
+
+    #[test]
+    fn cleared_manager_keeps_issuing_new_ids() {
+        let mut node_manager = NodeManager::new();
+        let ids = node_manager.insert_nodes(&[Node::default(); 4]);
+
+        node_manager.clear();
+        assert_eq!(node_manager.get_values().len(), 0);
+        assert!(ids.iter().all(|id| node_manager.get_node(id).is_none()));
+
+        let fresh = node_manager.insert(Node::default());
+        assert!(ids.iter().all(|id| fresh.0 > id.0));
+    }
+
+    #[test]
+    fn hanging_chain_settles() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 10, 20.);
+
+        // Slack between the anchor and target so the middle of the chain hangs
+        let ik =
+            InverseKinematic::new(nodes.clone(), Some(glam::Vec2::ZERO), glam::vec2(-120., 0.));
+        let mut physics = ChainPhysics::new(8, 0.02);
+        let gravity = glam::vec2(0., -300.);
+
+        let mut previous = node_manager.get_node_positions(&nodes);
+        let mut movement = f32::MAX;
+        (0..600).for_each(|_| {
+            physics.step(&mut node_manager, &nodes, gravity, 1. / 60.);
+            fabrik(&mut node_manager, &ik);
+
+            let current = node_manager.get_node_positions(&nodes);
+            movement = current
+                .iter()
+                .zip(&previous)
+                .map(|(a, b)| a.distance(*b))
+                .fold(0., f32::max);
+            previous = current;
+        });
+
+        assert!(movement < 0.05, "still moving {} per step", movement);
+        assert!(previous.iter().all(|pos| pos.is_finite()));
+
+        // Gravity still pulls the middle of the chain down
+        assert!(previous.iter().any(|pos| pos.y < -20.));
+    }
+
+    #[test]
+    fn find_nearest_picks_closest_in_range() {
+        let mut node_manager = NodeManager::new();
+        let ids = [glam::vec2(0., 0.), glam::vec2(30., 0.), glam::vec2(0., 50.)].map(|pos| {
+            node_manager.insert(Node {
+                pos,
+                ..Node::default()
+            })
+        });
+
+        assert_eq!(
+            node_manager.find_nearest(glam::vec2(20., 5.), 15.),
+            Some(ids[1])
+        );
+        assert_eq!(
+            node_manager.find_nearest(glam::vec2(5., 40.), 100.),
+            Some(ids[2])
+        );
+        assert_eq!(node_manager.find_nearest(glam::vec2(100., 100.), 20.), None);
+    }
+
+    #[test]
+    fn segment_length_overrides_radius() {
+        let parent = Node {
+            length: Some(100.),
+            ..Node::new(10.)
+        };
+        let mut child = Node {
+            pos: glam::vec2(3., 4.),
+            ..Node::new(10.)
+        };
+
+        attach_node(&parent, &mut child);
+        assert!((child.pos.distance(parent.pos) - 100.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn reachability_compares_target_to_chain_length() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 5, 20.);
+
+        let ik = |target: glam::Vec2| {
+            InverseKinematic::new(nodes.clone(), Some(glam::Vec2::ZERO), target)
+        };
+
+        assert_eq!(ik(glam::Vec2::ZERO).max_reach(&node_manager), 80.);
+        assert!(ik(glam::vec2(0., 70.)).is_reachable(&node_manager));
+        assert!(!ik(glam::vec2(0., 90.)).is_reachable(&node_manager));
+    }
+
+    #[test]
+    fn y_tree_orients_both_branches_from_root() {
+        let mut node_manager = NodeManager::new();
+        let root = node_manager.insert(Node::new(20.));
+        let left = node_manager.insert_nodes(&[Node::locked(10., 0.5); 2]);
+        let right = node_manager.insert_nodes(&[Node::locked(10., -0.5); 2]);
+
+        // Start every branch node somewhere the root can't have put it
+        left.iter().chain(&right).for_each(|id| {
+            node_manager.get_node_mut(id).unwrap().pos = glam::vec2(5., 40.);
+        });
+
+        let mut tree = ForwardKinematicTree::default();
+        tree.push(root, None);
+        tree.push_branch(&left, Some(0));
+        tree.push_branch(&right, Some(0));
+        process_fk_tree(&mut node_manager, &tree);
+
+        [(&left, 0.5), (&right, -0.5)]
+            .into_iter()
+            .for_each(|(branch, bend)| {
+                let first = node_manager.get_node(&branch[0]).unwrap();
+                let second = node_manager.get_node(&branch[1]).unwrap();
+
+                assert!(angle_diff(first.rotation, bend).abs() < 1e-4);
+                assert!(angle_diff(second.rotation, bend * 2.).abs() < 1e-4);
+                assert!(first.pos.distance(-glam::Vec2::from_angle(bend) * 20.) < 1e-3);
+                assert!(
+                    second
+                        .pos
+                        .distance(first.pos - glam::Vec2::from_angle(bend * 2.) * 10.)
+                        < 1e-3
+                );
+            });
+    }
+
+    #[test]
+    fn iter_ordered_follows_insertion_order() {
+        let mut node_manager = NodeManager::new();
+        let mut ids = (0..6)
+            .map(|index| node_manager.insert(Node::new(index as f32 + 1.)))
+            .collect::<Vec<_>>();
+
+        node_manager.remove(&ids.remove(2));
+        ids.extend((0..3).map(|_| node_manager.insert(Node::default())));
+
+        let ordered = node_manager
+            .iter_ordered()
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        assert_eq!(ordered, ids);
+    }
+
+    #[test]
+    fn opposite_poles_mirror_the_middle_node() {
+        let solve = |pole: glam::Vec2| {
+            let mut node_manager = NodeManager::new();
+            let nodes = [
+                glam::vec2(0., 0.),
+                glam::vec2(-20., 1.),
+                glam::vec2(-40., 0.),
+            ]
+            .map(|pos| {
+                node_manager.insert(Node {
+                    pos,
+                    ..Node::unlocked(20.)
+                })
+            });
+
+            let ik = InverseKinematic {
+                cycles: 50,
+                ..InverseKinematic::new(nodes.to_vec(), Some(glam::Vec2::ZERO), glam::vec2(30., 0.))
+            }
+            .with_pole(pole)
+            .with_tolerance(0.01);
+            fabrik(&mut node_manager, &ik);
+
+            node_manager.get_node(&nodes[1]).unwrap().pos
+        };
+
+        let above = solve(glam::vec2(15., 30.));
+        let below = solve(glam::vec2(15., -30.));
+
+        assert!(above.y > 0., "{}", above);
+        assert!(
+            above.distance(below * glam::vec2(1., -1.)) < 0.1,
+            "{} {}",
+            above,
+            below
+        );
+    }
+
+    #[test]
+    fn relax_converges_to_rest_rotations() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 5, 20.);
+        let rests = [0., 0.4, -0.3, 0.6, 0.2];
+
+        nodes.iter().zip(rests).for_each(|(id, rest)| {
+            node_manager.get_node_mut(id).unwrap().rest_rotation = rest;
+        });
+
+        (0..100).for_each(|_| relax(&mut node_manager, &nodes, 0.2));
+
+        nodes.windows(2).zip(&rests[1..]).for_each(|(pair, rest)| {
+            let parent = node_manager.get_node(&pair[0]).unwrap();
+            let child = node_manager.get_node(&pair[1]).unwrap();
+
+            let offset = angle_diff(child.rotation, parent.rotation);
+            assert!(
+                (offset - rest).abs() < 1e-3,
+                "offset {} rest {}",
+                offset,
+                rest
+            );
+            assert!((parent.pos.distance(child.pos) - parent.segment_length()).abs() < 1e-3);
+        });
+    }
+
+    #[test]
+    fn chain_aabb_expands_by_radius() {
+        let mut node_manager = NodeManager::new();
+        let ids = [
+            (glam::vec2(0., 0.), 5.),
+            (glam::vec2(40., -10.), 10.),
+            (glam::vec2(-20., 30.), 2.),
+        ]
+        .map(|(pos, radius)| {
+            node_manager.insert(Node {
+                pos,
+                ..Node::new(radius)
+            })
+        });
+
+        assert_eq!(
+            chain_aabb(&node_manager, &ids),
+            (glam::vec2(-22., -20.), glam::vec2(50., 32.))
+        );
+        assert_eq!(
+            chain_aabb(&node_manager, &[]),
+            (glam::Vec2::ZERO, glam::Vec2::ZERO)
+        );
+    }
+
+    #[test]
+    fn mirror_nodes_swaps_limits_and_negates_locks() {
+        let mirrored = mirror_nodes(&[Node::angles(20., -0.8, 0.3), Node::locked(15., 0.4)]);
+
+        assert_eq!(mirrored[0].min_rotation, -0.3);
+        assert_eq!(mirrored[0].max_rotation, 0.8);
+        assert_eq!(mirrored[0].radius, 20.);
+        assert_eq!(mirrored[1].min_rotation, -0.4);
+        assert_eq!(mirrored[1].max_rotation, -0.4);
+    }
+
+    #[test]
+    fn budgeted_chains_share_one_pool() {
+        let mut node_manager = NodeManager::new();
+
+        // Neither chain can finish early, so each uses every cycle it is allowed
+        let chains = [glam::vec2(-60., 40.), glam::vec2(50., -30.)].map(|target| {
+            let nodes = curled_chain(&mut node_manager, 6, 20.);
+            InverseKinematic::builder(nodes)
+                .anchor(glam::Vec2::ZERO)
+                .target(target)
+                .tolerance(0.)
+                .min_progress(0.)
+                .cycles(10)
+                .build()
+        });
+
+        let mut budget = SolverBudget::new(15);
+        chains.iter().for_each(|ik| {
+            budget.fabrik(&mut node_manager, ik);
+        });
+        assert!(budget.is_exhausted());
+
+        // The second chain only got what the first left behind
+        let mut remaining = 15;
+        fabrik_budgeted(&mut node_manager, &chains[0], &mut remaining);
+        assert_eq!(remaining, 5);
+        fabrik_budgeted(&mut node_manager, &chains[1], &mut remaining);
+        assert_eq!(remaining, 0);
+        assert!(!fabrik_budgeted(
+            &mut node_manager,
+            &chains[0],
+            &mut remaining
+        ));
+    }
+
+    #[test]
+    fn insert_chain_tapers_radii() {
+        let mut node_manager = NodeManager::new();
+        let nodes = node_manager.insert_chain(5, 50., 10.);
+
+        let radii = nodes
+            .iter()
+            .map(|id| node_manager.get_node(id).unwrap().radius)
+            .collect::<Vec<_>>();
+        assert_eq!(radii, [50., 40., 30., 20., 10.]);
+    }
+
+    #[test]
+    fn cone_is_centred_on_its_offset() {
+        let cone = Node::cone(10., 0.5, 0.2);
+        assert!((cone.min_rotation - 0.3).abs() < 1e-6);
+        assert!((cone.max_rotation - 0.7).abs() < 1e-6);
+
+        let centred = Node::cone(10., 0., 0.4);
+        let angle = Node::angle(10., 0.4);
+        assert_eq!(centred.min_rotation, angle.min_rotation);
+        assert_eq!(centred.max_rotation, angle.max_rotation);
+    }
+
+    #[test]
+    fn removed_ids_stay_invalid_after_more_inserts() {
+        let mut node_manager = NodeManager::new();
+        let kept = node_manager.insert(Node::new(10.));
+        let removed = node_manager.insert(Node::new(20.));
+
+        assert!(node_manager.remove(&removed).is_some());
+        let inserted = node_manager.insert_nodes(&[Node::new(30.), Node::new(40.)]);
+
+        assert!(!inserted.contains(&removed));
+        assert!(node_manager.get_node(&removed).is_none());
+        assert!(node_manager.get_node_mut(&removed).is_none());
+        assert!(node_manager.remove(&removed).is_none());
+        assert!(node_manager.is_stale(&removed));
+
+        assert!(!node_manager.is_stale(&kept));
+        assert_eq!(node_manager.get_node(&kept).unwrap().radius, 10.);
+    }
+
+    #[test]
+    fn spring_lags_then_converges_on_rigid_pose() {
+        let chain = |node_manager: &mut NodeManager| {
+            let nodes = node_manager.insert_nodes(&[Node::angle(20., 0.3); 3]);
+            nodes.iter().enumerate().for_each(|(index, id)| {
+                node_manager.get_node_mut(id).unwrap().pos = glam::vec2(index as f32 * -20., 0.);
+            });
+            ForwardKinematic { nodes }
+        };
+        // Turning the root further than a child can bend forces it to swing round
+        let root_override = Some((glam::Vec2::ZERO, 1.));
+
+        let mut rigid_manager = NodeManager::new();
+        let rigid = chain(&mut rigid_manager);
+        process_fk(&mut rigid_manager, &rigid, root_override);
+        let rigid = rigid_manager.get_node(&rigid.nodes[1]).unwrap().pos;
+
+        let mut node_manager = NodeManager::new();
+        let fk = chain(&mut node_manager);
+        let mut spring = ChainSpring::new(20.);
+        let mut step = || {
+            spring.process(&mut node_manager, &fk, root_override, 1. / 60.);
+            node_manager
+                .get_node(&fk.nodes[1])
+                .unwrap()
+                .pos
+                .distance(rigid)
+        };
+
+        let first = step();
+        assert!(first > 1., "reached the rigid pose in one step");
+
+        let last = (0..120).map(|_| step()).last().unwrap();
+        assert!(last < first);
+        assert!(last < 0.05, "still {} away after two seconds", last);
+    }
+
+    #[test]
+    fn builder_matches_manual_construction() {
+        let mut node_manager = NodeManager::new();
+        let nodes = node_manager.insert_nodes(&[Node::new(40.); 12]);
+
+        let built = InverseKinematic::builder(nodes.clone())
+            .anchor(glam::vec2(0., -100.))
+            .build();
+        let manual = InverseKinematic {
+            nodes,
+            anchor: Some(glam::vec2(0., -100.)),
+            target: TargetSource::Fixed(glam::Vec2::ZERO),
+            targets: Vec::new(),
+            cycles: 10,
+            tolerance: InverseKinematic::DEFAULT_TOLERANCE,
+            pole: None,
+            anchor_locked: true,
+            solver: SolverKind::Fabrik,
+            smoothing: 0.,
+            min_progress: InverseKinematic::DEFAULT_MIN_PROGRESS,
+        };
+
+        assert_eq!(format!("{:?}", built), format!("{:?}", manual));
+    }
+
+    #[test]
+    fn unlocked_anchor_swivels_towards_chain() {
+        let solve = |locked: bool| {
+            let mut node_manager = NodeManager::new();
+            let nodes = curled_chain(&mut node_manager, 5, 20.);
+            let ik = InverseKinematic::builder(nodes.clone())
+                .anchor(glam::Vec2::ZERO)
+                .target(glam::vec2(10., 60.))
+                .anchor_locked(locked)
+                .build();
+            fabrik(&mut node_manager, &ik);
+
+            let [root, child] = [0, 1].map(|index| *node_manager.get_node(&nodes[index]).unwrap());
+            (root.rotation, (root.pos - child.pos).to_angle())
+        };
+
+        let (locked, _) = solve(true);
+        assert_eq!(locked, 0.);
+
+        let (unlocked, facing) = solve(false);
+        assert!(angle_diff(unlocked, locked).abs() > 0.1);
+        assert!(angle_diff(unlocked, facing).abs() < 1e-4);
+    }
+
+    #[test]
+    fn wrap_angle_stays_in_range() {
+        let specials = [
+            -4.,
+            PI,
+            -PI,
+            TAU,
+            -TAU,
+            3. * TAU,
+            -3. * TAU,
+            PI + TAU,
+            -PI - TAU,
+        ];
+        let sweep = (-100..=100).map(|step| step as f32 * 0.37);
+
+        specials.into_iter().chain(sweep).for_each(|angle| {
+            let wrapped = wrap_angle(angle);
+            assert!(
+                wrapped > -PI && wrapped <= PI,
+                "{} wrapped to {}",
+                angle,
+                wrapped
+            );
+
+            // Wrapping only ever adds whole turns
+            let turns = (angle - wrapped) / TAU;
+            assert!(
+                (turns - turns.round()).abs() < 1e-3,
+                "{} wrapped to {}",
+                angle,
+                wrapped
+            );
+        });
+
+        assert!((wrap_angle(-4.) - (TAU - 4.)).abs() < 1e-5);
+        assert_eq!(wrap_angle(-PI), PI);
+    }
+
+    #[test]
+    fn angle_diff_is_antisymmetric() {
+        let angles = (-20..=20).map(|step| step as f32 * 0.3);
+
+        angles.clone().for_each(|a| {
+            angles.clone().for_each(|b| {
+                let diff = angle_diff(a, b);
+
+                // Exactly opposite angles could go either way round
+                if (diff.abs() - PI).abs() < 1e-3 {
+                    return;
+                }
+
+                assert!(
+                    (diff + angle_diff(b, a)).abs() < 1e-5,
+                    "angle_diff({}, {}) = {}",
+                    a,
+                    b,
+                    diff
+                );
+            });
+        });
+    }
+
+    #[test]
+    fn solve_with_fabrik_matches_fabrik() {
+        let run = |use_solve: bool| {
+            let mut node_manager = NodeManager::new();
+            let nodes = curled_chain(&mut node_manager, 6, 20.);
+            let ik = InverseKinematic::builder(nodes.clone())
+                .anchor(glam::Vec2::ZERO)
+                .target(glam::vec2(-40., 70.))
+                .solver(SolverKind::Fabrik)
+                .build();
+
+            let reached = match use_solve {
+                true => ik.solve(&mut node_manager).reached,
+                false => fabrik(&mut node_manager, &ik),
+            };
+            (reached, node_manager.get_node_positions(&nodes))
+        };
+
+        assert_eq!(run(true), run(false));
+    }
+
+    #[test]
+    fn smoothing_eases_towards_new_target() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 6, 20.);
+        let tip = *nodes.last().unwrap();
+
+        let from = glam::vec2(-80., 20.);
+        let ik = InverseKinematic::builder(nodes)
+            .anchor(glam::Vec2::ZERO)
+            .target(from)
+            .cycles(40)
+            .build();
+        assert!(fabrik(&mut node_manager, &ik));
+        let start = node_manager.get_node(&tip).unwrap().pos;
+
+        let to = glam::vec2(-80., -20.);
+        let ik = ik.with_smoothing(0.9);
+        let ik = InverseKinematic {
+            target: to.into(),
+            ..ik
+        };
+
+        // Only about a tenth of the move happens in one solve, so the target isn't reached yet
+        assert!(!fabrik(&mut node_manager, &ik));
+        let moved = node_manager.get_node(&tip).unwrap().pos.distance(start);
+        let full = start.distance(to);
+        assert!(
+            (moved / full - 0.1).abs() < 0.05,
+            "tip moved {} of {}",
+            moved,
+            full
+        );
+    }
+
+    #[test]
+    fn reversed_chain_reaches_back_to_the_anchor() {
+        let (start, end) = (glam::Vec2::ZERO, glam::vec2(50., 30.));
+
+        let chain = |node_manager: &mut NodeManager| {
+            let nodes = curled_chain(node_manager, 5, 20.);
+            nodes.iter().enumerate().for_each(|(index, id)| {
+                node_manager.get_node_mut(id).unwrap().radius = 10. + index as f32 * 5.;
+            });
+            nodes
+        };
+        let segments = |node_manager: &NodeManager, nodes: &[NodeID]| {
+            node_manager
+                .get_node_positions(nodes)
+                .windows(2)
+                .map(|pair| pair[0].distance(pair[1]))
+                .collect::<Vec<_>>()
+        };
+
+        let mut node_manager = NodeManager::new();
+        let nodes = chain(&mut node_manager);
+        let ik = InverseKinematic::builder(nodes)
+            .anchor(start)
+            .target(end)
+            .build();
+        assert!(fabrik(&mut node_manager, &ik));
+        let lengths = segments(&node_manager, &ik.nodes);
+
+        // Solve the same chain from the other end, reaching back to where it was anchored
+        let mut reversed_manager = NodeManager::new();
+        let nodes = chain(&mut reversed_manager);
+        let mut reversed = InverseKinematic::builder(nodes)
+            .anchor(start)
+            .target(end)
+            .build()
+            .reversed(&mut reversed_manager);
+        (reversed.anchor, reversed.target) = (Some(end), start.into());
+        assert!(fabrik(&mut reversed_manager, &reversed));
+
+        let positions = reversed_manager.get_node_positions(&reversed.nodes);
+        assert_eq!(positions[0], end);
+        assert!(positions.last().unwrap().distance(start) < reversed.tolerance);
+
+        segments(&reversed_manager, &reversed.nodes)
+            .iter()
+            .rev()
+            .zip(&lengths)
+            .for_each(|(a, b)| assert!((a - b).abs() < 1e-3, "{} != {}", a, b));
+    }
+
+    #[test]
+    fn reversing_only_stores_lengths_that_differ_from_radius() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 4, 20.);
+        let mut fk = ForwardKinematic {
+            nodes: nodes.clone(),
+        };
+
+        // Equal radii already give every segment the right length
+        fk.reverse(&mut node_manager);
+        nodes.iter().for_each(|id| {
+            assert_eq!(node_manager.get_node(id).unwrap().length, None);
+        });
+
+        // So scaling afterwards still follows the radii
+        node_manager.scale_nodes(&fk.nodes, 2.);
+        nodes.iter().for_each(|id| {
+            assert_eq!(node_manager.get_node(id).unwrap().segment_length(), 40.);
+        });
+
+        // Uneven radii store lengths where needed and reversing back clears them again
+        node_manager.get_node_mut(&nodes[1]).unwrap().radius = 10.;
+        fk.reverse(&mut node_manager);
+        let lengths = nodes
+            .iter()
+            .map(|id| node_manager.get_node(id).unwrap().length)
+            .collect::<Vec<_>>();
+        assert_eq!(lengths, [Some(10.), Some(40.), None, None]);
+
+        fk.reverse(&mut node_manager);
+        nodes.iter().for_each(|id| {
+            assert_eq!(node_manager.get_node(id).unwrap().length, None);
+        });
+    }
+
+    #[test]
+    fn unreachable_target_stops_once_settled() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 5, 20.);
+        let ik = InverseKinematic::builder(nodes)
+            .anchor(glam::Vec2::ZERO)
+            .target(glam::vec2(300., 200.))
+            .cycles(1000)
+            .build();
+
+        let result = Fabrik.solve(&mut node_manager, &ik);
+        assert!(!result.reached);
+        assert!(result.iterations < 100, "used {} cycles", result.iterations);
+
+        // Stopping early still leaves the chain stretched towards the target
+        let tip = node_manager.get_node_positions(&ik.nodes)[4];
+        assert!((tip.length() - 80.).abs() < 0.5);
+        assert!(tip.normalize().dot(glam::vec2(300., 200.).normalize()) > 0.99);
+    }
+
+    #[test]
+    fn restoring_a_snapshot_undoes_changes() {
+        let mut node_manager = NodeManager::new();
+        let nodes = curled_chain(&mut node_manager, 4, 20.);
+        let ik = InverseKinematic::builder(nodes.clone())
+            .anchor(glam::Vec2::ZERO)
+            .target(glam::vec2(30., 40.))
+            .build();
+        let pose = |node_manager: &NodeManager| {
+            nodes
+                .iter()
+                .map(|id| {
+                    let node = node_manager.get_node(id).unwrap();
+                    (node.pos, node.rotation)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let snapshot = node_manager.snapshot_positions(&nodes);
+        let before = pose(&node_manager);
+
+        fabrik(&mut node_manager, &ik);
+        assert_ne!(pose(&node_manager), before);
+
+        node_manager.restore_positions(&snapshot);
+        assert_eq!(pose(&node_manager), before);
+    }
 }
@@ -0,0 +1,80 @@
+// A tiny seven segment stroke font, drawn with the line pipeline for debug labels.
+// Only digits, `-`, `.`, `:` and spaces are supported. Other characters are drawn as a box.
+
+// Segments of a glyph one unit wide and two units tall, starting from the bottom left
+const SEGMENTS: [(glam::Vec2, glam::Vec2); 7] = [
+    (glam::vec2(0., 2.), glam::vec2(1., 2.)), // a - top
+    (glam::vec2(1., 2.), glam::vec2(1., 1.)), // b - top right
+    (glam::vec2(1., 1.), glam::vec2(1., 0.)), // c - bottom right
+    (glam::vec2(0., 0.), glam::vec2(1., 0.)), // d - bottom
+    (glam::vec2(0., 1.), glam::vec2(0., 0.)), // e - bottom left
+    (glam::vec2(0., 2.), glam::vec2(0., 1.)), // f - top left
+    (glam::vec2(0., 1.), glam::vec2(1., 1.)), // g - middle
+];
+
+/// Space between the start of each glyph, relative to the glyph width
+const ADVANCE: f32 = 1.5;
+
+/// Bitmask of lit segments for a character, with bit 0 being segment a
+fn segment_mask(character: char) -> Option<u8> {
+    match character {
+        '0' => Some(0b0111111),
+        '1' => Some(0b0000110),
+        '2' => Some(0b1011011),
+        '3' => Some(0b1001111),
+        '4' => Some(0b1100110),
+        '5' => Some(0b1101101),
+        '6' => Some(0b1111101),
+        '7' => Some(0b0000111),
+        '8' => Some(0b1111111),
+        '9' => Some(0b1101111),
+        '-' => Some(0b1000000),
+        _ => None,
+    }
+}
+
+// Short strokes for punctuation that doesn't fit the segments
+const DOT: [(glam::Vec2, glam::Vec2); 1] = [(glam::vec2(0.4, 0.), glam::vec2(0.6, 0.))];
+const COLON: [(glam::Vec2, glam::Vec2); 2] = [
+    (glam::vec2(0.4, 1.5), glam::vec2(0.6, 1.5)),
+    (glam::vec2(0.4, 0.5), glam::vec2(0.6, 0.5)),
+];
+
+fn punctuation(character: char) -> Option<&'static [(glam::Vec2, glam::Vec2)]> {
+    match character {
+        ' ' => Some(&[]),
+        '.' => Some(&DOT),
+        ':' => Some(&COLON),
+        _ => None,
+    }
+}
+
+/// Line segments spelling out `text`, with the bottom left of the first glyph at `pos`.
+/// `height` is the height of a glyph in world units.
+pub fn text_segments(text: &str, pos: glam::Vec2, height: f32) -> Vec<(glam::Vec2, glam::Vec2)> {
+    let scale = height / 2.;
+
+    text.chars()
+        .enumerate()
+        .flat_map(|(index, character)| {
+            let origin = pos + glam::vec2(index as f32 * ADVANCE * scale, 0.);
+            let to_world = move |(from, to): (glam::Vec2, glam::Vec2)| {
+                (origin + from * scale, origin + to * scale)
+            };
+
+            let strokes = match (segment_mask(character), punctuation(character)) {
+                (Some(mask), _) => SEGMENTS
+                    .iter()
+                    .enumerate()
+                    .filter(|(segment, _)| mask & (1 << segment) != 0)
+                    .map(|(_, stroke)| *stroke)
+                    .collect::<Vec<_>>(),
+                (None, Some(strokes)) => strokes.to_vec(),
+                // Unknown characters are boxed so they're still visible
+                (None, None) => [0, 1, 2, 3, 4, 5].map(|segment| SEGMENTS[segment]).to_vec(),
+            };
+
+            strokes.into_iter().map(to_world)
+        })
+        .collect()
+}
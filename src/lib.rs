@@ -1,8 +1,8 @@
 use core::f32;
 use std::time::Duration;
 
-use ik::NodeManager;
-use renderer::{CircleInstance, Renderer};
+use ik::ChainRegistry;
+use renderer::Renderer;
 use roots_core::{
     common::{
         input::{self, Input, MouseInput},
@@ -15,15 +15,26 @@ use roots_core::{
         RunnerState, WindowInputEvent,
     },
 };
-use substates::SubState;
+use substates::FixedTimestep;
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
-mod ik;
+pub mod creature_spec;
+pub mod ik;
+mod labels;
 mod polygon_manager;
+#[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+mod recorder;
 mod renderer;
 mod substates;
 
+pub use ik::{
+    angle_diff, fabrik, process_fk, process_fk_tree, relax, wrap_angle, ForwardKinematic,
+    ForwardKinematicTree, InverseKinematic, Node, NodeManager, SolverKind,
+};
+pub use renderer::{CircleInstance, NullRenderer, PolygonInstance, PolygonVertex, RenderContext};
+pub use substates::{default_substates, SimTime, Substate, SubstateConstructor};
+
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen(start))]
 pub fn run() {
     roots_core::runner::Runner::<State>::run(Some(&[
@@ -74,7 +85,8 @@ impl RunnerState for State {
     }
 
     fn resized(&mut self, new_size: Size<u32>) {
-        self.renderer.resize(new_size);
+        self.renderer
+            .resize(new_size, self.window.inner().scale_factor() as f32);
         self.window_size = new_size;
     }
 
@@ -98,17 +110,34 @@ pub struct State {
     mouse_input: MouseInput,
 
     node_manager: NodeManager,
-    substate: SubState,
+    /// Every substate currently running. The last one is the most recently added
+    substates: Vec<Box<dyn Substate>>,
+    /// Constructors cycled through when adding or changing substates
+    substate_kinds: Vec<SubstateConstructor>,
+    /// Index into `substate_kinds` of the next substate to create
+    next_kind: usize,
+    show_angle_limits: bool,
+    show_skeleton: bool,
+    show_labels: bool,
+    /// Whether polygons were drawn before switching to outlines, restored when switching back
+    polygons_before_outlines: bool,
+    chain_registry: ChainRegistry,
+    timestep: FixedTimestep,
 }
 
 impl State {
+    /// Simulation runs at 60 steps per second regardless of frame rate
+    const FIXED_STEP: f32 = 1. / 60.;
+    const MAX_STEPS_PER_FRAME: usize = 5;
+
     fn new(window: Window) -> Self {
-        let renderer = Renderer::new(&window);
+        let mut renderer = Renderer::new(&window);
         let window_size = window.size();
 
         let mut node_manager = NodeManager::new();
 
-        let substate = SubState::new_ik(&mut node_manager);
+        let substate_kinds = substates::default_substates();
+        let substate = substate_kinds[0](&mut node_manager, &mut renderer);
 
         Self {
             window,
@@ -125,7 +154,15 @@ impl State {
             mouse_input: Default::default(),
 
             node_manager,
-            substate,
+            substates: vec![substate],
+            substate_kinds,
+            next_kind: 1,
+            show_angle_limits: false,
+            show_skeleton: false,
+            show_labels: false,
+            polygons_before_outlines: true,
+            chain_registry: ChainRegistry::default(),
+            timestep: FixedTimestep::new(Self::FIXED_STEP, Self::MAX_STEPS_PER_FRAME),
         }
     }
 
@@ -136,6 +173,14 @@ impl State {
             self.change_state();
         }
 
+        if self.keys.just_pressed(KeyCode::Tab) {
+            self.add_state();
+        }
+
+        if self.keys.just_pressed(KeyCode::Backspace) {
+            self.remove_state();
+        }
+
         if self.keys.just_pressed(KeyCode::Digit1) {
             self.renderer.render_circles = !self.renderer.render_circles;
             if !self.renderer.render_circles {
@@ -154,27 +199,177 @@ impl State {
             self.window.hide_cursor(true);
         }
 
-        // Change from winit coordinates (winit 0,0 starts top left) to camera coords (0, 0) screen centre
-        let mouse_pos = glam::vec2(
-            self.mouse_input.position().x,
-            self.window_size.height as f32 - self.mouse_input.position().y,
-        ) - glam::vec2(
-            self.window_size.width as f32,
-            self.window_size.height as f32,
-        ) / 2.;
+        if self.keys.just_pressed(KeyCode::Digit4) {
+            log::info!("{:?}", self.renderer.frame_stats());
+        }
 
-        self.substate
-            .update(&self.time, &mut self.node_manager, mouse_pos);
+        if self.keys.just_pressed(KeyCode::Digit5) {
+            self.renderer.set_vsync(!self.renderer.vsync());
+        }
 
-        // Render all nodes
-        self.node_manager.get_values().into_iter().for_each(|node| {
-            self.renderer
-                .circle_pipeline
-                .prep_circle(CircleInstance::new(node.pos, node.radius).hollow());
+        if self.keys.just_pressed(KeyCode::Digit6) {
+            self.show_angle_limits = !self.show_angle_limits;
+        }
+
+        if self.keys.just_pressed(KeyCode::Digit8) {
+            self.show_skeleton = !self.show_skeleton;
+        }
+
+        if self.keys.just_pressed(KeyCode::KeyL) {
+            self.show_labels = !self.show_labels;
+        }
+
+        if self.keys.just_pressed(KeyCode::KeyG) {
+            let strength = match self.renderer.gradient_strength() > 0. {
+                true => 0.,
+                false => Renderer::GRADIENT_STRENGTH,
+            };
+            self.renderer.set_gradient_strength(strength);
+        }
+
+        // Outlines replace the filled polygons until toggled off again
+        if self.keys.just_pressed(KeyCode::Digit9) {
+            self.renderer.render_outlines = !self.renderer.render_outlines;
+            match self.renderer.render_outlines {
+                true => {
+                    self.polygons_before_outlines = self.renderer.render_polygons;
+                    self.renderer.render_polygons = false;
+                }
+                false => self.renderer.render_polygons = self.polygons_before_outlines,
+            }
+        }
+
+        #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+        self.update_captures();
+
+        if self.keys.just_pressed(KeyCode::Digit0) {
+            self.renderer.camera_controller_mut().reset();
+        }
+
+        self.substates.iter_mut().for_each(|substate| {
+            let Some(mut scale) = substate.scale() else {
+                return;
+            };
+
+            if self.keys.just_pressed(KeyCode::Equal) {
+                scale *= 1.1;
+            }
+            if self.keys.just_pressed(KeyCode::Minus) {
+                scale /= 1.1;
+            }
+
+            if Some(scale) != substate.scale() {
+                substate.set_scale(&mut self.node_manager, scale);
+            }
+        });
+
+        self.renderer
+            .update_camera_controller(&self.mouse_input, &self.mouse_buttons);
+
+        let mouse_pos = self
+            .renderer
+            .screen_to_world(self.mouse_input.position(), self.window_size);
+
+        // Edge triggered input is only handled once, however many steps run this frame
+        self.substates.iter_mut().for_each(|substate| {
+            substate.handle_input(
+                &mut self.node_manager,
+                mouse_pos,
+                &self.mouse_buttons,
+                &self.keys,
+            )
+        });
+
+        let steps = self.timestep.advance(self.time.delta_seconds());
+        (0..steps).for_each(|_| {
+            self.substates.iter_mut().for_each(|substate| {
+                substate.update(
+                    self.timestep.sim_time(),
+                    &mut self.node_manager,
+                    mouse_pos,
+                    &self.mouse_buttons,
+                    &self.keys,
+                )
+            })
+        });
+
+        self.chain_registry.clear();
+        self.substates
+            .iter()
+            .for_each(|substate| substate.report_chains(&mut self.chain_registry));
+
+        if self.keys.just_pressed(KeyCode::Digit7) {
+            log::info!(
+                "{} chains, {} unreached\n{}",
+                self.chain_registry.len(),
+                self.chain_registry.unreached_count(),
+                self.chain_registry.summary()
+            );
+        }
+
+        // Render all nodes, alternating outline colors along each chain
+        self.substates
+            .iter()
+            .flat_map(|substate| substate.chains())
+            .for_each(|chain| {
+                chain.iter().enumerate().for_each(|(index, id)| {
+                    let Some(node) = self.node_manager.get_node(id) else {
+                        return;
+                    };
+
+                    self.renderer.circle_pipeline.prep_circle(
+                        CircleInstance::new(node.pos, node.radius)
+                            .hollow()
+                            .with_border(6., substates::outline_color(index)),
+                    );
+                })
+            });
+
+        // Skeleton links between node centres
+        if self.show_skeleton {
+            self.substates
+                .iter()
+                .flat_map(|substate| substate.chains())
+                .for_each(|chain| {
+                    let points = self.node_manager.get_node_positions(chain);
+
+                    self.renderer
+                        .line_pipeline
+                        .prep_strip(&points, glam::vec4(1., 1., 0., 1.));
+                });
+        }
+
+        // Node ids and rotations in degrees
+        if self.show_labels {
+            self.substates
+                .iter()
+                .flat_map(|substate| substate.chains())
+                .flatten()
+                .for_each(|id| {
+                    let Some(node) = self.node_manager.get_node(id) else {
+                        return;
+                    };
+
+                    self.renderer.prep_label(
+                        node.pos,
+                        format!("{}:{:.0}", id, node.rotation.to_degrees()),
+                    );
+                });
+        }
+
+        if self.show_angle_limits {
+            self.render_angle_limits();
+        }
+
+        self.substates.iter_mut().for_each(|substate| {
+            substate.render(&self.node_manager, &mut self.renderer, mouse_pos)
         });
 
-        self.substate
-            .render(&mut self.node_manager, &mut self.renderer, mouse_pos);
+        if self.renderer.render_outlines {
+            self.substates.iter().for_each(|substate| {
+                substate.render_outlines(&self.node_manager, &mut self.renderer)
+            });
+        }
 
         // Input management
         input::reset_input(&mut self.keys);
@@ -183,26 +378,168 @@ impl State {
     }
 
     fn render(&mut self) {
-        self.renderer.prep();
+        self.renderer.prep(self.time.delta_seconds());
         self.renderer.render();
+
+        #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+        self.renderer.process_captures();
     }
 
-    fn change_state(&mut self) {
-        self.node_manager = NodeManager::new();
+    /// F12 saves a screenshot and F11 starts or stops recording a gif
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    fn update_captures(&mut self) {
+        const RECORDING_FRAMES: usize = 150;
 
-        match self.substate {
-            SubState::IK(_) => {
-                self.substate = SubState::new_fk(&mut self.node_manager, &mut self.renderer);
-            }
-            SubState::FK(_) => {
-                self.substate = SubState::new_creature(&mut self.node_manager, &mut self.renderer);
-            }
-            SubState::Creature(_) => {
-                self.substate = SubState::new_bridge(&mut self.node_manager, &mut self.renderer);
-            }
-            SubState::Bridge(_) => {
-                self.substate = SubState::new_ik(&mut self.node_manager);
+        if self.keys.just_pressed(KeyCode::F12) {
+            let timestamp = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            self.renderer
+                .request_screenshot(format!("screenshot_{}.png", timestamp));
+        }
+
+        if self.keys.just_pressed(KeyCode::F11) {
+            match self.renderer.is_recording() {
+                true => self.renderer.stop_recording(),
+                false => self
+                    .renderer
+                    .start_recording(RECORDING_FRAMES, "recording.gif"),
             }
         }
     }
+
+    /// Draw a wedge at each parent node showing where its child is allowed to sit
+    fn render_angle_limits(&mut self) {
+        const LIMIT_COLOR: glam::Vec4 = glam::vec4(0.3, 0.8, 0.4, 1.);
+
+        self.substates
+            .iter()
+            .flat_map(|substate| substate.chains())
+            .for_each(|chain| {
+                chain.windows(2).for_each(|pair| {
+                    let (Some(parent), Some(child)) = (
+                        self.node_manager.get_node(&pair[0]),
+                        self.node_manager.get_node(&pair[1]),
+                    ) else {
+                        return;
+                    };
+
+                    // Unlocked nodes can sit anywhere around their parent
+                    let Some((min, max)) = angle_limit_arc(parent, child) else {
+                        self.renderer.circle_pipeline.prep_circle(
+                            CircleInstance::new(parent.pos, parent.radius)
+                                .hollow()
+                                .with_border(1., LIMIT_COLOR),
+                        );
+                        return;
+                    };
+
+                    self.renderer.circle_pipeline.prep_circle(
+                        CircleInstance::new(parent.pos, parent.radius)
+                            .hollow()
+                            .with_border(1., LIMIT_COLOR)
+                            .with_arc(min, max),
+                    );
+
+                    [min, max].into_iter().for_each(|angle| {
+                        self.renderer.line_pipeline.prep_line(
+                            parent.pos,
+                            parent.pos + glam::Vec2::from_angle(angle) * parent.radius,
+                            LIMIT_COLOR,
+                        )
+                    });
+                })
+            });
+    }
+
+    /// Replace the newest substate with the next one in the cycle
+    fn change_state(&mut self) {
+        let Some(substate) = self.substates.pop() else {
+            return;
+        };
+
+        substate.remove_nodes(&mut self.node_manager);
+        let substate = self.create_next_state();
+        self.substates.push(substate);
+
+        self.fade_to_newest_state();
+    }
+
+    /// Add the substate after the newest one, keeping every existing substate running
+    fn add_state(&mut self) {
+        let substate = self.create_next_state();
+        self.substates.push(substate);
+
+        self.fade_to_newest_state();
+    }
+
+    /// Remove the newest substate, always keeping at least one
+    fn remove_state(&mut self) {
+        if self.substates.len() <= 1 {
+            return;
+        }
+
+        if let Some(substate) = self.substates.pop() {
+            substate.remove_nodes(&mut self.node_manager);
+        }
+
+        self.fade_to_newest_state();
+    }
+
+    /// Add a substate to the cycle used by space and tab
+    #[inline]
+    pub fn register_substate(&mut self, constructor: SubstateConstructor) {
+        self.substate_kinds.push(constructor);
+    }
+
+    fn create_next_state(&mut self) -> Box<dyn Substate> {
+        let constructor = self.substate_kinds[self.next_kind % self.substate_kinds.len()];
+        self.next_kind = (self.next_kind + 1) % self.substate_kinds.len();
+        constructor(&mut self.node_manager, &mut self.renderer)
+    }
+
+    #[inline]
+    fn fade_to_newest_state(&mut self) {
+        if let Some(substate) = self.substates.last() {
+            self.renderer.fade_clear_color(substate.clear_color());
+        }
+    }
+}
+
+/// Start and end angles of the wedge around `parent` where `child` is allowed to sit,
+/// or `None` if the child is unlocked and can sit anywhere.
+fn angle_limit_arc(parent: &Node, child: &Node) -> Option<(f32, f32)> {
+    if child.max_rotation - child.min_rotation >= f32::consts::TAU {
+        return None;
+    }
+
+    // Children face towards their parent, so the allowed area is on the opposite side
+    let facing = parent.rotation + f32::consts::PI;
+    Some((facing + child.min_rotation, facing + child.max_rotation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn angle_limit_arc_spans_child_limits() {
+        let parent = Node {
+            rotation: 0.5,
+            ..Node::new(10.)
+        };
+        let child = Node {
+            min_rotation: -0.4,
+            max_rotation: 0.8,
+            ..Node::new(10.)
+        };
+
+        let (start, end) = angle_limit_arc(&parent, &child).unwrap();
+        assert!((start - (0.1 + f32::consts::PI)).abs() < 1e-5);
+        assert!((end - (1.3 + f32::consts::PI)).abs() < 1e-5);
+        assert!(end > start);
+
+        assert_eq!(angle_limit_arc(&parent, &Node::unlocked(10.)), None);
+    }
 }
@@ -38,12 +38,42 @@ impl PolygonNode {
     }
 }
 
-#[derive(Default)]
+/// Scales a node's rendered radius based on its normalized position along the chain (0 at head, 1 at tail)
+pub type RadiusProfile = fn(f32) -> f32;
+
+/// Profile that tapers both ends of the chain, leaving the middle at full width
+pub fn thickest_in_middle(t: f32) -> f32 {
+    0.4 + 0.6 * (t * f32::consts::PI).sin()
+}
+
 pub struct PolygonManager {
     custom_nodes: HashMap<NodeID, PolygonNode>,
+    base_color: glam::Vec4,
+    radius_profile: Option<RadiusProfile>,
+    cap_segments: usize,
+    closed: bool,
+}
+
+impl Default for PolygonManager {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            custom_nodes: HashMap::default(),
+            base_color: glam::Vec4::ONE,
+            radius_profile: None,
+            cap_segments: 0,
+            closed: false,
+        }
+    }
 }
 
 impl PolygonManager {
+    /// Set the color used when no color is passed to `calculate_vertices`
+    #[inline]
+    pub fn with_base_color(&mut self, color: glam::Vec4) {
+        self.base_color = color;
+    }
+
     #[inline]
     pub fn with_custom(&mut self, nodes: Vec<(NodeID, PolygonNode)>) {
         nodes.into_iter().for_each(|(id, node)| {
@@ -51,11 +81,33 @@ impl PolygonManager {
         });
     }
 
+    /// Set a profile used to scale node radii along the chain
+    #[inline]
+    pub fn with_radius_profile(&mut self, profile: RadiusProfile) {
+        self.radius_profile = Some(profile);
+    }
+
+    /// Round off the head and tail with this many extra vertices each.
+    /// 0 gives the default pointed ends.
+    #[inline]
+    pub fn with_cap_segments(&mut self, segments: usize) {
+        self.cap_segments = segments;
+    }
+
+    /// Connect the last node back to the first to form a ring instead of a strip with ends.
+    /// Only used by `calculate_vertices`.
+    #[inline]
+    pub fn with_closed(&mut self, closed: bool) {
+        self.closed = closed;
+    }
+
+    /// Build the triangles for a chain. Each node's color comes from its custom polygon node
+    /// if set, then `Node::color`, then the given colors or the base color.
     pub fn calculate_vertices(
         &self,
         node_manager: &NodeManager,
         nodes: &[NodeID],
-        color: glam::Vec4,
+        color: Option<glam::Vec4>,
         start_color: Option<glam::Vec4>,
         end_color: Option<glam::Vec4>,
     ) -> (Vec<PolygonVertex>, Vec<u16>) {
@@ -63,13 +115,174 @@ impl PolygonManager {
             panic!("No nodes provided to calculate vertices");
         }
 
-        let start_color = start_color.unwrap_or(color);
-        let end_color = end_color.unwrap_or(color);
+        let color = color.unwrap_or(self.base_color);
+
+        // Blend smoothly down the chain when both ends are given
+        let gradient = |t: f32| match (start_color, end_color) {
+            (Some(start), Some(end)) => start.lerp(end, t),
+            _ => color,
+        };
+
+        let mut rings = self.node_rings(node_manager, nodes, gradient);
+        self.apply_profile(&mut rings);
+
+        if self.closed {
+            return build_closed_vertices(&rings);
+        }
+
+        let head = node_manager.get_node(&nodes[0]).unwrap();
+        let tail = node_manager.get_node(nodes.last().unwrap()).unwrap();
+
+        // Caps match their neighbouring ring so there's no seam, unless a color is given
+        build_vertices(
+            &rings,
+            (
+                head.get_relative_point(0.),
+                start_color.unwrap_or(rings[0].color),
+            ),
+            (
+                tail.get_relative_point(f32::consts::PI),
+                end_color.unwrap_or(rings[rings.len() - 1].color),
+            ),
+            self.cap_segments,
+        )
+    }
+
+    /// Calculate vertices along a Catmull-Rom spline fitted through the node centres.
+    /// This only affects rendering, letting a low resolution chain look continuous.
+    pub fn calculate_smoothed_vertices(
+        &self,
+        node_manager: &NodeManager,
+        nodes: &[NodeID],
+        subdivisions: usize,
+        color: Option<glam::Vec4>,
+    ) -> (Vec<PolygonVertex>, Vec<u16>) {
+        if nodes.is_empty() {
+            panic!("No nodes provided to calculate vertices");
+        }
+
+        let color = color.unwrap_or(self.base_color);
+        let subdivisions = subdivisions.max(1);
+
+        let node_rings = self.node_rings(node_manager, nodes, |_| color);
+        let points = node_rings.iter().map(|ring| ring.pos).collect::<Vec<_>>();
+        let points = catmull_rom(&points, subdivisions);
+
+        let last = points.len() - 1;
+
+        let mut rings = points
+            .iter()
+            .enumerate()
+            .map(|(index, pos)| {
+                let segment = (index / subdivisions).min(node_rings.len() - 1);
+                let next = (segment + 1).min(node_rings.len() - 1);
+                let t = (index % subdivisions) as f32 / subdivisions as f32;
+
+                let a = &node_rings[segment];
+                let b = &node_rings[next];
+
+                // Rotations face towards the head of the chain
+                let rotation = if index == 0 {
+                    a.rotation
+                } else if index == last {
+                    b.rotation
+                } else {
+                    (points[index - 1] - points[index + 1]).to_angle()
+                };
+
+                Ring {
+                    pos: *pos,
+                    rotation,
+                    radius: a.radius + (b.radius - a.radius) * t,
+                    color: a.color.lerp(b.color, t),
+                    depth: a.depth + (b.depth - a.depth) * t,
+                    t: a.t + (b.t - a.t) * t,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.apply_profile(&mut rings);
+
+        let head = node_manager.get_node(&nodes[0]).unwrap();
+        let tail = node_manager.get_node(nodes.last().unwrap()).unwrap();
+
+        build_vertices(
+            &rings,
+            (head.get_relative_point(0.), rings[0].color),
+            (
+                tail.get_relative_point(f32::consts::PI),
+                rings[rings.len() - 1].color,
+            ),
+            self.cap_segments,
+        )
+    }
+
+    /// Calculate the outline of a chain as a closed perimeter walk.
+    /// Goes forward down the left side, around the tail, back up the right side and around the head,
+    /// finishing on the starting point. Uses the same custom nodes and radius profile as `calculate_vertices`,
+    /// with `radius_overrides` replacing the radius of any of the given nodes before the profile is applied.
+    pub fn outline_path(
+        &self,
+        node_manager: &NodeManager,
+        nodes: &[NodeID],
+        radius_overrides: &[(NodeID, f32)],
+    ) -> Vec<glam::Vec2> {
+        if nodes.is_empty() {
+            return Vec::new();
+        }
+
+        let mut rings = self.node_rings(node_manager, nodes, |_| self.base_color);
+
+        radius_overrides.iter().for_each(|(id, radius)| {
+            if let Some(index) = nodes.iter().position(|node| node == id) {
+                rings[index].radius = *radius;
+            }
+        });
+
+        self.apply_profile(&mut rings);
+
+        let head = node_manager.get_node(&nodes[0]).unwrap();
+        let tail = node_manager.get_node(nodes.last().unwrap()).unwrap();
 
-        let mut vertices = nodes
+        let side = |ring: &Ring, offset: f32| {
+            glam::Vec2::from_angle(ring.rotation + offset) * ring.radius + ring.pos
+        };
+
+        let mut path = rings
             .iter()
-            .flat_map(|node_id| {
+            .map(|ring| side(ring, -f32::consts::FRAC_PI_2))
+            .collect::<Vec<_>>();
+
+        path.push(tail.get_relative_point(f32::consts::PI));
+        path.extend(
+            rings
+                .iter()
+                .rev()
+                .map(|ring| side(ring, f32::consts::FRAC_PI_2)),
+        );
+        path.push(head.get_relative_point(0.));
+        path.push(path[0]);
+
+        path
+    }
+
+    /// Get the cross section of each node. `color` gives the color at a fraction along the chain,
+    /// which the node's own color and then custom nodes can override.
+    fn node_rings(
+        &self,
+        node_manager: &NodeManager,
+        nodes: &[NodeID],
+        color: impl Fn(f32) -> glam::Vec4,
+    ) -> Vec<Ring> {
+        let last_index = (nodes.len().max(2) - 1) as f32;
+
+        nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node_id)| {
                 let node = node_manager.get_node(node_id).unwrap();
+                let t = index as f32 / last_index;
+                let color = node.color.unwrap_or_else(|| color(t));
 
                 let (radius, color) = match self.custom_nodes.get(node_id) {
                     Some(PolygonNode {
@@ -83,56 +296,636 @@ impl PolygonManager {
                     None => (node.radius, color),
                 };
 
-                [
-                    PolygonVertex {
-                        pos: glam::Vec2::from_angle(node.rotation - f32::consts::FRAC_PI_2)
-                            * radius
-                            + node.pos,
-                        pad: [0; 2],
-                        color,
-                    },
-                    PolygonVertex {
-                        pos: glam::Vec2::from_angle(node.rotation + f32::consts::FRAC_PI_2)
-                            * radius
-                            + node.pos,
-                        pad: [0; 2],
-                        color,
-                    },
-                ]
+                Ring {
+                    pos: node.pos,
+                    rotation: node.rotation,
+                    radius,
+                    color,
+                    depth: node.z,
+                    t,
+                }
             })
-            .collect::<Vec<_>>();
+            .collect()
+    }
 
-        let head = node_manager.get_node(&nodes[0]).unwrap();
-        vertices.insert(
-            0,
-            PolygonVertex {
-                pos: head.get_relative_point(0.),
-                pad: [0; 2],
-                color: start_color,
-            },
+    fn apply_profile(&self, rings: &mut [Ring]) {
+        let Some(profile) = self.radius_profile else {
+            return;
+        };
+
+        let last_index = (rings.len().max(2) - 1) as f32;
+
+        rings.iter_mut().enumerate().for_each(|(index, ring)| {
+            ring.radius *= profile(index as f32 / last_index);
+        });
+    }
+}
+
+/// Direction from a ring towards the ring before it (towards the head of the chain).
+/// Falls back to the ring's rotation if the neighbouring rings overlap.
+fn segment_direction(rings: &[Ring], index: usize) -> glam::Vec2 {
+    let direction = match index {
+        0 => rings.get(1).map(|next| rings[0].pos - next.pos),
+        _ => Some(rings[index - 1].pos - rings[index].pos),
+    };
+
+    direction
+        .and_then(|direction| direction.try_normalize())
+        .unwrap_or_else(|| glam::Vec2::from_angle(rings[index].rotation))
+}
+
+/// A cross section of a polygon chain
+struct Ring {
+    pos: glam::Vec2,
+    rotation: f32,
+    radius: f32,
+    color: glam::Vec4,
+    depth: f32,
+    /// Fraction along the chain, from 0 at the head to 1 at the tail
+    t: f32,
+}
+
+/// Build a triangle strip through the rings, starting at the head point and ending at the tail point.
+/// With `cap_segments` > 0 the ends are rounded with a fan instead of meeting at a point.
+fn build_vertices(
+    rings: &[Ring],
+    head: (glam::Vec2, glam::Vec4),
+    tail: (glam::Vec2, glam::Vec4),
+    cap_segments: usize,
+) -> (Vec<PolygonVertex>, Vec<u16>) {
+    // Rounded caps are fanned out from the end ring centres
+    let (head, tail) = match (cap_segments, rings.first(), rings.last()) {
+        (1.., Some(first), Some(last)) => ((first.pos, head.1), (last.pos, tail.1)),
+        _ => (head, tail),
+    };
+
+    let mut vertices = rings
+        .iter()
+        .enumerate()
+        .flat_map(|(index, ring)| {
+            let mut edges = [
+                PolygonVertex {
+                    pos: glam::Vec2::from_angle(ring.rotation - f32::consts::FRAC_PI_2)
+                        * ring.radius
+                        + ring.pos,
+                    depth: ring.depth,
+                    t: ring.t,
+                    color: ring.color,
+                },
+                PolygonVertex {
+                    pos: glam::Vec2::from_angle(ring.rotation + f32::consts::FRAC_PI_2)
+                        * ring.radius
+                        + ring.pos,
+                    depth: ring.depth,
+                    t: ring.t,
+                    color: ring.color,
+                },
+            ];
+
+            // Keep the left edge on the left of the chain so every quad has the same winding,
+            // even when a ring's rotation doesn't match the direction of the chain
+            if segment_direction(rings, index).perp_dot(edges[0].pos - ring.pos) > 0. {
+                edges.swap(0, 1);
+            }
+
+            edges
+        })
+        .collect::<Vec<_>>();
+
+    untwist_edges(&mut vertices);
+
+    // End points take the depth of their closest ring
+    let head_depth = rings.first().map_or(0., |ring| ring.depth);
+    let tail_depth = rings.last().map_or(0., |ring| ring.depth);
+
+    vertices.insert(
+        0,
+        PolygonVertex {
+            pos: head.0,
+            depth: head_depth,
+            t: 0.,
+            color: head.1,
+        },
+    );
+
+    vertices.push(PolygonVertex {
+        pos: tail.0,
+        depth: tail_depth,
+        t: 1.,
+        color: tail.1,
+    });
+
+    let mut indices = (3..vertices.len())
+        .step_by(2)
+        .fold(Vec::new(), |mut acc, index| {
+            acc.push(index as u16 - 3); // 0
+            acc.push(index as u16 - 2); // 1
+            acc.push(index as u16 - 1); // 2
+
+            acc.push(index as u16 - 1); // 2
+            acc.push(index as u16 - 2); // 1
+            acc.push(index as u16); // 3
+
+            acc
+        });
+
+    if let (Some(first), Some(last), 1..) = (rings.first(), rings.last(), cap_segments) {
+        let tail_index = vertices.len() - 1;
+
+        // Head cap sweeps around the front from the left side to the right side
+        add_cap(
+            &mut vertices,
+            &mut indices,
+            first,
+            first.rotation - f32::consts::FRAC_PI_2,
+            head.1,
+            (0, 1, 2),
+            cap_segments,
         );
 
-        let tail = node_manager.get_node(nodes.last().unwrap()).unwrap();
-        vertices.push(PolygonVertex {
-            pos: tail.get_relative_point(f32::consts::PI),
-            pad: [0; 2],
-            color: end_color,
+        // Tail cap sweeps around the back from the right side to the left side
+        add_cap(
+            &mut vertices,
+            &mut indices,
+            last,
+            last.rotation + f32::consts::FRAC_PI_2,
+            tail.1,
+            (tail_index, tail_index - 1, tail_index - 2),
+            cap_segments,
+        );
+    }
+
+    (vertices, indices)
+}
+
+/// On a sharp bend the inner edge of a ring can land in front of the ring before it, which
+/// twists the quad between them. Pull those vertices back onto the previous ring's edge.
+/// `edges` holds a left and right vertex for each ring, in chain order.
+fn untwist_edges(edges: &mut [PolygonVertex]) {
+    (2..edges.len()).step_by(2).for_each(|index| {
+        let start = edges[index - 2].pos;
+        let across = edges[index - 1].pos - start;
+        if across.length_squared() <= f32::EPSILON {
+            return;
+        }
+
+        // The left edge is kept on the left, so this points away from the head
+        let back = across.perp();
+
+        edges[index..index + 2].iter_mut().for_each(|vertex| {
+            let offset = vertex.pos - start;
+            if offset.dot(back) < 0. {
+                let t = (offset.dot(across) / across.length_squared()).clamp(0., 1.);
+                vertex.pos = start + across * t;
+            }
+        });
+    });
+}
+
+/// Build a loop of quads through the rings, with the last ring joining back to the first
+fn build_closed_vertices(rings: &[Ring]) -> (Vec<PolygonVertex>, Vec<u16>) {
+    let vertices = rings
+        .iter()
+        .flat_map(|ring| {
+            [-f32::consts::FRAC_PI_2, f32::consts::FRAC_PI_2].map(|offset| PolygonVertex {
+                pos: glam::Vec2::from_angle(ring.rotation + offset) * ring.radius + ring.pos,
+                depth: ring.depth,
+                t: ring.t,
+                color: ring.color,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    // A loop needs at least three rings to enclose anything
+    if rings.len() < 3 {
+        return (vertices, Vec::new());
+    }
+
+    let indices = (0..rings.len())
+        .flat_map(|index| {
+            let current = index as u16 * 2;
+            let next = ((index + 1) % rings.len()) as u16 * 2;
+
+            [current, current + 1, next, next, current + 1, next + 1]
+        })
+        .collect();
+
+    (vertices, indices)
+}
+
+/// Add a half circle fan of triangles around a ring.
+/// `fan` is the (centre, start side, end side) vertex indices the cap connects to.
+fn add_cap(
+    vertices: &mut Vec<PolygonVertex>,
+    indices: &mut Vec<u16>,
+    ring: &Ring,
+    start_angle: f32,
+    color: glam::Vec4,
+    fan: (usize, usize, usize),
+    segments: usize,
+) {
+    let (centre, start, end) = fan;
+    let first_new = vertices.len();
+
+    vertices.extend((1..=segments).map(|segment| {
+        let angle = start_angle + f32::consts::PI * segment as f32 / (segments + 1) as f32;
+
+        PolygonVertex {
+            pos: glam::Vec2::from_angle(angle) * ring.radius + ring.pos,
+            depth: ring.depth,
+            t: ring.t,
+            color,
+        }
+    }));
+
+    let points = std::iter::once(start)
+        .chain(first_new..vertices.len())
+        .chain(std::iter::once(end))
+        .collect::<Vec<_>>();
+
+    points.windows(2).for_each(|pair| {
+        indices.push(centre as u16);
+        indices.push(pair[0] as u16);
+        indices.push(pair[1] as u16);
+    });
+}
+
+/// Fit a Catmull-Rom spline through the given points.
+/// Returns `(points - 1) * subdivisions + 1` points, starting and ending on the original end points.
+pub fn catmull_rom(points: &[glam::Vec2], subdivisions: usize) -> Vec<glam::Vec2> {
+    if points.len() < 2 || subdivisions <= 1 {
+        return points.to_vec();
+    }
+
+    let last = points.len() - 1;
+
+    let mut smoothed = (0..last)
+        .flat_map(|index| {
+            let p0 = points[index.saturating_sub(1)];
+            let p1 = points[index];
+            let p2 = points[index + 1];
+            let p3 = points[(index + 2).min(last)];
+
+            (0..subdivisions).map(move |step| {
+                let t = step as f32 / subdivisions as f32;
+                let t2 = t * t;
+                let t3 = t2 * t;
+
+                0.5 * ((2. * p1)
+                    + (p2 - p0) * t
+                    + (2. * p0 - 5. * p1 + 4. * p2 - p3) * t2
+                    + (3. * p1 - p0 - 3. * p2 + p3) * t3)
+            })
+        })
+        .collect::<Vec<_>>();
+
+    smoothed.push(points[last]);
+
+    smoothed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ik::Node;
+
+    /// Insert a straight chain heading in the given direction from the origin
+    fn straight_chain(node_manager: &mut NodeManager, heading: f32, count: usize) -> Vec<NodeID> {
+        // Nodes face back towards their parent
+        let rotation = heading + f32::consts::PI;
+
+        (0..count)
+            .map(|index| {
+                node_manager.insert(Node {
+                    pos: glam::Vec2::from_angle(heading) * index as f32 * 20.,
+                    rotation,
+                    ..Node::new(20.)
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn base_color_is_used_without_passed_color() {
+        let mut polygons = PolygonManager::default();
+        let base = glam::vec4(1., 0., 0., 1.);
+        polygons.with_base_color(base);
+
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0., 3);
+
+        let (vertices, _) = polygons.calculate_vertices(&node_manager, &nodes, None, None, None);
+        assert!(vertices.iter().all(|vertex| vertex.color == base));
+
+        let passed = glam::vec4(0., 0., 1., 1.);
+        let (vertices, _) =
+            polygons.calculate_vertices(&node_manager, &nodes, Some(passed), None, None);
+        assert!(vertices.iter().all(|vertex| vertex.color == passed));
+    }
+
+    #[test]
+    fn thickest_in_middle_profile_widens_middle() {
+        let mut polygons = PolygonManager::default();
+        polygons.with_radius_profile(thickest_in_middle);
+
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0., 5);
+
+        // Each node contributes a pair of side vertices after the head point
+        let (vertices, _) = polygons.calculate_vertices(&node_manager, &nodes, None, None, None);
+        let widths = vertices[1..vertices.len() - 1]
+            .chunks(2)
+            .map(|pair| pair[0].pos.distance(pair[1].pos))
+            .collect::<Vec<_>>();
+
+        assert!(widths[2] > widths[0], "{:?}", widths);
+        assert!(widths[2] > widths[4], "{:?}", widths);
+        assert!((widths[2] - 40.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn catmull_rom_passes_through_nodes() {
+        let points = [
+            glam::vec2(0., 0.),
+            glam::vec2(20., 10.),
+            glam::vec2(40., -5.),
+            glam::vec2(60., 0.),
+            glam::vec2(70., 20.),
+        ];
+        let subdivisions = 4;
+
+        let smoothed = catmull_rom(&points, subdivisions);
+        assert_eq!(smoothed.len(), (points.len() - 1) * subdivisions + 1);
+        assert_eq!(smoothed[0], points[0]);
+        assert_eq!(*smoothed.last().unwrap(), points[points.len() - 1]);
+
+        // Every subdivision-th point lands back on a node
+        points.iter().enumerate().for_each(|(index, point)| {
+            assert!(smoothed[index * subdivisions].distance(*point) < 1e-4);
         });
+    }
+
+    /// Twice the signed area enclosed by a closed path, positive when counter clockwise
+    fn signed_area(path: &[glam::Vec2]) -> f32 {
+        path.windows(2).map(|pair| pair[0].perp_dot(pair[1])).sum()
+    }
+
+    #[test]
+    fn outline_path_is_closed_and_winds_consistently() {
+        let polygons = PolygonManager::default();
+
+        [0., 1., 2.5, -2.].into_iter().for_each(|heading| {
+            let mut node_manager = NodeManager::new();
+            let nodes = straight_chain(&mut node_manager, heading, 4);
+
+            let path = polygons.outline_path(&node_manager, &nodes, &[]);
+            assert_eq!(path.len(), nodes.len() * 2 + 3);
+            assert!(path[0].distance(*path.last().unwrap()) < 1e-4);
+
+            // A 60 long, 40 wide body with a triangular point at each end
+            let area = signed_area(&path);
+            assert!(area < 0., "heading {} winds the other way", heading);
+            assert!((area.abs() / 2. - (60. * 40. + 2. * 20. * 20.)).abs() < 1e-1);
+        });
+    }
+
+    #[test]
+    fn outline_path_walks_left_side_then_right_side_back() {
+        let polygons = PolygonManager::default();
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0., 3);
+
+        let path = polygons.outline_path(&node_manager, &nodes, &[]);
+        let expected = [
+            // Left edge from head to tail
+            glam::vec2(0., 20.),
+            glam::vec2(20., 20.),
+            glam::vec2(40., 20.),
+            // Tail point
+            glam::vec2(60., 0.),
+            // Right edge from tail back to head
+            glam::vec2(40., -20.),
+            glam::vec2(20., -20.),
+            glam::vec2(0., -20.),
+            // Head point, then back to the start
+            glam::vec2(-20., 0.),
+            glam::vec2(0., 20.),
+        ];
+
+        assert_eq!(path.len(), expected.len());
+        path.iter().zip(expected).for_each(|(point, expected)| {
+            assert!(point.distance(expected) < 1e-3, "{} != {}", point, expected);
+        });
+    }
+
+    #[test]
+    fn outline_path_uses_radius_overrides() {
+        let polygons = PolygonManager::default();
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0., 3);
+
+        let path = polygons.outline_path(&node_manager, &nodes, &[(nodes[1], 5.)]);
+
+        // Left side walks forward through the rings
+        assert!((path[0].y.abs() - 20.).abs() < 1e-4);
+        assert!((path[1].y.abs() - 5.).abs() < 1e-4);
+        assert!((path[2].y.abs() - 20.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn caps_add_vertices_per_segment() {
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0.7, 4);
+
+        let count = |cap_segments: usize| {
+            let mut polygons = PolygonManager::default();
+            polygons.with_cap_segments(cap_segments);
+            polygons
+                .calculate_vertices(&node_manager, &nodes, None, None, None)
+                .0
+                .len()
+        };
+
+        [1, 4, 8].into_iter().for_each(|cap_segments| {
+            let added = count(cap_segments) as i32 - count(0) as i32;
+            assert!(
+                (added - 2 * cap_segments as i32).abs() <= 2,
+                "{} cap segments added {} vertices",
+                cap_segments,
+                added
+            );
+        });
+    }
+
+    #[test]
+    fn middle_segment_gets_midpoint_color() {
+        let polygons = PolygonManager::default();
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0., 5);
+
+        let start = glam::vec4(0., 0., 0., 1.);
+        let end = glam::vec4(1., 0.5, 0.2, 1.);
+        let (vertices, _) =
+            polygons.calculate_vertices(&node_manager, &nodes, None, Some(start), Some(end));
+
+        // The middle node sits at x = 40 with its sides above and below
+        let middle = vertices
+            .iter()
+            .filter(|vertex| (vertex.pos.x - 40.).abs() < 1e-3 && vertex.pos.y.abs() > 1.)
+            .collect::<Vec<_>>();
+        assert!(!middle.is_empty());
+        middle.into_iter().for_each(|vertex| {
+            assert!(
+                vertex.color.distance(start.lerp(end, 0.5)) < 1e-4,
+                "{:?}",
+                vertex
+            );
+        });
+    }
+
+    #[test]
+    fn closed_ring_wraps_back_to_first_vertices() {
+        let mut polygons = PolygonManager::default();
+        polygons.with_closed(true);
+
+        let mut node_manager = NodeManager::new();
+        let nodes = (0..6)
+            .map(|index| {
+                let angle = index as f32 / 6. * f32::consts::TAU;
+                node_manager.insert(Node {
+                    pos: glam::Vec2::from_angle(angle) * 50.,
+                    rotation: angle + f32::consts::FRAC_PI_2,
+                    ..Node::new(10.)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let (vertices, indices) =
+            polygons.calculate_vertices(&node_manager, &nodes, None, None, None);
+        assert_eq!(vertices.len(), 12);
+        assert_eq!(indices.len(), 36);
+
+        let last_pair = &indices[30..];
+        assert!(last_pair.contains(&0));
+        assert!(last_pair.contains(&1));
+        assert!(last_pair.contains(&10));
+        assert!(last_pair.contains(&11));
+    }
+
+    #[test]
+    fn sharp_bend_keeps_triangle_winding() {
+        let polygons = PolygonManager::default();
+
+        [90_f32, 140., 170.].into_iter().for_each(|bend| {
+            let mut node_manager = NodeManager::new();
+
+            // The second segment turns by `bend` degrees, folding back towards the first
+            let elbow = glam::vec2(-20., 0.);
+            let positions = [
+                glam::Vec2::ZERO,
+                elbow,
+                elbow + glam::Vec2::from_angle((180. - bend).to_radians()) * 20.,
+            ];
+            let nodes = positions
+                .iter()
+                .enumerate()
+                .map(|(index, pos)| {
+                    let rotation = match index {
+                        0 => 0.,
+                        _ => (positions[index - 1] - *pos).to_angle(),
+                    };
+                    node_manager.insert(Node {
+                        pos: *pos,
+                        rotation,
+                        ..Node::new(15.)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            let (vertices, indices) =
+                polygons.calculate_vertices(&node_manager, &nodes, None, None, None);
+            let areas = indices
+                .chunks(3)
+                .map(|triangle| {
+                    let [a, b, c] = [0, 1, 2].map(|corner| vertices[triangle[corner] as usize].pos);
+                    (b - a).perp_dot(c - a)
+                })
+                .filter(|area| area.abs() > 1e-3)
+                .collect::<Vec<_>>();
+
+            assert!(!areas.is_empty());
+            assert!(
+                areas.iter().all(|area| area.signum() == areas[0].signum()),
+                "bend {} gave {:?}",
+                bend,
+                areas
+            );
+        });
+    }
+
+    #[test]
+    fn head_matches_first_segment_color() {
+        let polygons = PolygonManager::default();
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0., 4);
+
+        let head_color = glam::vec4(0.2, 0.9, 0.4, 1.);
+
+        // The head point comes first, followed by the first ring's left and right vertices
+        let (vertices, _) =
+            polygons.calculate_vertices(&node_manager, &nodes, Some(head_color), None, None);
+        assert_eq!(vertices[0].color, head_color);
+        assert_eq!(vertices[1].color, head_color);
+        assert_eq!(vertices[2].color, head_color);
+
+        let start = glam::vec4(1., 0., 0., 1.);
+        let (vertices, _) =
+            polygons.calculate_vertices(&node_manager, &nodes, Some(head_color), Some(start), None);
+        assert_eq!(vertices[0].color, start);
+        assert_eq!(vertices[1].color, head_color);
+    }
+
+    #[test]
+    fn gradient_runs_from_head_to_tail() {
+        let polygons = PolygonManager::default();
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0., 4);
+
+        let (mut vertices, _) =
+            polygons.calculate_vertices(&node_manager, &nodes, None, None, None);
+        vertices.sort_by(|a, b| a.pos.x.total_cmp(&b.pos.x));
+
+        // The chain heads along x, so t should only grow further along it
+        assert_eq!(vertices.first().unwrap().t, 0.);
+        assert_eq!(vertices.last().unwrap().t, 1.);
+        vertices.windows(2).for_each(|pair| {
+            assert!(
+                pair[0].t <= pair[1].t + 1e-5,
+                "{:?} then {:?}",
+                pair[0],
+                pair[1]
+            );
+        });
+    }
 
-        let indices = (3..vertices.len())
-            .step_by(2)
-            .fold(Vec::new(), |mut acc, index| {
-                acc.push(index as u16 - 3); // 0
-                acc.push(index as u16 - 2); // 1
-                acc.push(index as u16 - 1); // 2
+    #[test]
+    fn custom_color_beats_node_color_beats_passed_color() {
+        let custom = glam::vec4(1., 0., 0., 1.);
+        let tint = glam::vec4(0., 1., 0., 1.);
+        let passed = glam::vec4(0., 0., 1., 1.);
 
-                acc.push(index as u16 - 1); // 2
-                acc.push(index as u16 - 2); // 1
-                acc.push(index as u16); // 3
+        let mut node_manager = NodeManager::new();
+        let nodes = straight_chain(&mut node_manager, 0., 3);
+        node_manager.get_node_mut(&nodes[0]).unwrap().color = Some(tint);
+        node_manager.get_node_mut(&nodes[1]).unwrap().color = Some(tint);
 
-                acc
-            });
+        let mut polygons = PolygonManager::default();
+        polygons.with_custom(vec![(nodes[0], PolygonNode::color(custom))]);
 
-        (vertices, indices)
+        // Skip the head point, then each ring has a left and right vertex
+        let (vertices, _) =
+            polygons.calculate_vertices(&node_manager, &nodes, Some(passed), None, None);
+        let ring_colors = [1, 3, 5].map(|index| vertices[index].color);
+        assert_eq!(ring_colors, [custom, tint, passed]);
     }
 }
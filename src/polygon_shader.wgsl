@@ -8,17 +8,30 @@ struct Camera {
 
 @group(0) @binding(0) var<uniform> camera: Camera;
 
+struct Gradient {
+    // How much darker the tail of a chain is than the head.
+    // 0 keeps the vertex colors, raise it for a subsurface look
+    strength: f32,
+}
+
+@group(1) @binding(0) var<uniform> gradient: Gradient;
+
+// Node z values between -500 and 500 map to the full depth range
+const DEPTH_SCALE: f32 = 0.001;
+
 //====================================================================
 
 struct VertexIn {
     // Vertex
-    @location(0) vertex_pos: vec2<f32>,
+    // Position in xy, depth in z and gradient coordinate in w
+    @location(0) vertex_pos: vec4<f32>,
     @location(1) vertex_color: vec4<f32>,
 }
 
 struct VertexOut {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(1) t: f32,
 }
 
 //====================================================================
@@ -29,17 +42,23 @@ fn vs_main(in: VertexIn) -> VertexOut {
 
     out.clip_position =
         camera.projection
-        * vec4<f32>(in.vertex_pos, 0., 1.);
+        * vec4<f32>(in.vertex_pos.xy, 0., 1.);
+
+    // Higher depth values are closer to the camera
+    out.clip_position.z = clamp(0.5 - in.vertex_pos.z * DEPTH_SCALE, 0., 1.) * out.clip_position.w;
 
 
     out.color = in.vertex_color;
+    out.t = in.vertex_pos.w;
 
     return out;
 }
 
 @fragment
 fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
-    return in.color;
+    // Darken slightly towards the tail of the chain
+    let shade = 1. - gradient.strength * in.t;
+    return vec4<f32>(in.color.rgb * shade, in.color.a);
 }
 
 //====================================================================
@@ -0,0 +1,129 @@
+use std::{fs::File, io::BufWriter, path::PathBuf};
+
+use image::{
+    codecs::gif::{GifEncoder, Repeat},
+    imageops::{self, FilterType},
+    Delay, Frame, RgbaImage,
+};
+
+/// Accumulates a fixed number of frames and encodes them into an animated gif
+pub struct FrameRecorder {
+    frames: Vec<RgbaImage>,
+    frame_limit: usize,
+    path: PathBuf,
+    max_width: u32,
+    frame_delay_ms: u32,
+}
+
+impl FrameRecorder {
+    const DEFAULT_MAX_WIDTH: u32 = 480;
+    const DEFAULT_FRAME_DELAY_MS: u32 = 33;
+
+    pub fn new(frame_limit: usize, path: impl Into<PathBuf>) -> Self {
+        Self {
+            frames: Vec::with_capacity(frame_limit),
+            frame_limit,
+            path: path.into(),
+            max_width: Self::DEFAULT_MAX_WIDTH,
+            frame_delay_ms: Self::DEFAULT_FRAME_DELAY_MS,
+        }
+    }
+
+    /// Frames wider than this are scaled down (keeping aspect ratio) to keep file sizes reasonable
+    #[cfg(test)]
+    #[inline]
+    pub fn with_max_width(mut self, max_width: u32) -> Self {
+        self.max_width = max_width.max(1);
+        self
+    }
+
+    #[cfg(test)]
+    #[inline]
+    pub fn with_frame_delay(mut self, frame_delay_ms: u32) -> Self {
+        self.frame_delay_ms = frame_delay_ms;
+        self
+    }
+
+    #[cfg(test)]
+    #[inline]
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    #[inline]
+    pub fn is_full(&self) -> bool {
+        self.frames.len() >= self.frame_limit
+    }
+
+    /// Add a frame to the recording, downsampling it if needed.
+    /// Frames past the limit are ignored. Returns true once the recording is full.
+    pub fn push_frame(&mut self, frame: RgbaImage) -> bool {
+        if self.is_full() {
+            return true;
+        }
+
+        let frame = match frame.width() > self.max_width {
+            true => {
+                let height = (frame.height() as u64 * self.max_width as u64 / frame.width() as u64)
+                    .max(1) as u32;
+                imageops::resize(&frame, self.max_width, height, FilterType::Triangle)
+            }
+            false => frame,
+        };
+
+        self.frames.push(frame);
+        self.is_full()
+    }
+
+    /// Encode all recorded frames to the output path
+    pub fn encode(self) -> image::ImageResult<()> {
+        log::info!(
+            "Encoding {} frames to '{}'",
+            self.frames.len(),
+            self.path.display()
+        );
+
+        let file = File::create(&self.path)?;
+        let mut encoder = GifEncoder::new(BufWriter::new(file));
+        encoder.set_repeat(Repeat::Infinite)?;
+
+        let delay = Delay::from_numer_denom_ms(self.frame_delay_ms, 1);
+        encoder.encode_frames(
+            self.frames
+                .into_iter()
+                .map(|frame| Frame::from_parts(frame, 0, 0, delay)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{codecs::gif::GifDecoder, AnimationDecoder};
+
+    #[test]
+    fn recorder_keeps_requested_frames() {
+        let path = std::env::temp_dir().join("ik_creatures_recorder_test.gif");
+        let mut recorder = FrameRecorder::new(3, &path)
+            .with_max_width(40)
+            .with_frame_delay(50);
+
+        let full = (0..5)
+            .map(|_| recorder.push_frame(RgbaImage::new(80, 60)))
+            .collect::<Vec<_>>();
+        assert_eq!(full, [false, false, true, true, true]);
+        assert_eq!(recorder.frame_count(), 3);
+
+        // Frames are scaled down to the max width, keeping their aspect ratio
+        assert!(recorder
+            .frames
+            .iter()
+            .all(|frame| frame.dimensions() == (40, 30)));
+
+        recorder.encode().unwrap();
+        let decoder = GifDecoder::new(std::io::BufReader::new(File::open(&path).unwrap())).unwrap();
+        assert_eq!(decoder.into_frames().count(), 3);
+
+        std::fs::remove_file(&path).ok();
+    }
+}
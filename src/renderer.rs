@@ -1,7 +1,15 @@
-use std::{cell::RefCell, ops::DerefMut, rc::Rc};
+use std::{
+    cell::RefCell,
+    f32::consts::TAU,
+    ops::{DerefMut, Range},
+    rc::Rc,
+};
 
 use roots_core::{
-    common::Size,
+    common::{
+        input::{Input, MouseInput},
+        Size,
+    },
     prelude::{
         camera::{Camera, OrthographicCamera},
         Color, Device, Queue, Surface, SurfaceConfig,
@@ -10,9 +18,82 @@ use roots_core::{
         shared::{SharedRenderResources, Vertex},
         tools, RenderCore, RenderEncoder, RenderPass, RenderPassDesc,
     },
-    runner::window::Window,
+    runner::{prelude::MouseButton, window::Window},
 };
 
+use crate::labels;
+
+#[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+use crate::recorder::FrameRecorder;
+
+/// Amount of geometry submitted in a frame
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    pub circle_instances: u32,
+    pub circle_capacity: u32,
+    pub polygon_vertices: u32,
+    pub polygon_indices: u32,
+    pub visible_polygons: usize,
+}
+
+impl FrameStats {
+    /// Read the geometry uploaded by the pipelines' last `finish_prep`
+    fn collect(circles: &CircleQueue, polygons: &PolygonInstances) -> Self {
+        let (polygon_vertices, polygon_indices) = polygons.geometry_count();
+
+        Self {
+            circle_instances: circles.instance_count(),
+            circle_capacity: circles.instance_capacity(),
+            polygon_vertices,
+            polygon_indices,
+            visible_polygons: polygons.visible_instance_count(),
+        }
+    }
+}
+
+/// Linear fade between two colors over a fixed duration (in seconds)
+#[derive(Debug, Clone, Copy)]
+pub struct ColorFade {
+    from: glam::Vec4,
+    to: glam::Vec4,
+    elapsed: f32,
+    duration: f32,
+}
+
+impl ColorFade {
+    #[inline]
+    pub fn new(from: glam::Vec4, to: glam::Vec4, duration: f32) -> Self {
+        Self {
+            from,
+            to,
+            elapsed: 0.,
+            duration,
+        }
+    }
+
+    /// Current color of the fade
+    #[inline]
+    pub fn current(&self) -> glam::Vec4 {
+        if self.finished() {
+            return self.to;
+        }
+
+        self.from.lerp(self.to, self.elapsed / self.duration)
+    }
+
+    #[inline]
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Advance the fade and return the new color
+    #[inline]
+    pub fn step(&mut self, delta_seconds: f32) -> glam::Vec4 {
+        self.elapsed += delta_seconds;
+        self.current()
+    }
+}
+
 pub struct Renderer {
     pub device: Device,
     pub queue: Queue,
@@ -23,15 +104,38 @@ pub struct Renderer {
 
     pub circle_pipeline: CirclePipeline,
     pub polygon_pipeline: PolygonPipeline,
+    pub line_pipeline: LinePipeline,
     pub render_circles: bool,
     pub render_polygons: bool,
+    pub render_lines: bool,
+    /// Draw polygons as line outlines. Used by the state when prepping lines
+    pub render_outlines: bool,
 
     pub clear_color: Color,
+    clear_color_fade: ColorFade,
     camera_data: OrthographicCamera,
     camera: Camera,
+    camera_controller: CameraController,
+    view_size: glam::Vec2,
+    frame_stats: FrameStats,
+    depth_view: wgpu::TextureView,
+    /// Text waiting to be turned into lines this frame
+    labels: Vec<(glam::Vec2, String)>,
+
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    recorder: Option<FrameRecorder>,
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    screenshot_path: Option<std::path::PathBuf>,
 }
 
 impl Renderer {
+    const CLEAR_COLOR_FADE_DURATION: f32 = 0.5;
+    /// Height of label text at the default zoom, in world units
+    const LABEL_HEIGHT: f32 = 12.;
+    const LABEL_COLOR: glam::Vec4 = glam::vec4(1., 1., 1., 1.);
+    /// Gradient strength used when the polygon gradient is switched on
+    pub const GRADIENT_STRENGTH: f32 = 0.2;
+
     pub fn new(window: &Window) -> Self {
         let (device, queue, surface, config) =
             RenderCore::new_blocked(window.clone_arc(), window.size())
@@ -41,11 +145,15 @@ impl Renderer {
         let shared = SharedRenderResources::new(&device);
         let circle_pipeline = CirclePipeline::new(&device, &config, &shared);
         let polygon_pipeline = PolygonPipeline::new(&device, &config, &shared);
+        let line_pipeline = LinePipeline::new(&device, &config, &shared);
 
         // let camera_data = OrthographicCamera::new_sized(1920., 1080.);
-        let camera_data = OrthographicCamera::new_centered(1920. / 2., 1080. / 2.);
+        let view_size = glam::vec2(1920., 1080.);
+        let camera_data = OrthographicCamera::new_centered(view_size.x / 2., view_size.y / 2.);
         let camera = Camera::new(&device, &camera_data, shared.camera_bind_group_layout());
 
+        let depth_view = create_depth_texture(&device, config.width, config.height);
+
         Self {
             device,
             queue,
@@ -55,56 +163,529 @@ impl Renderer {
             _shared: shared,
             circle_pipeline,
             polygon_pipeline,
+            line_pipeline,
             render_circles: true,
             render_polygons: true,
+            render_lines: true,
+            render_outlines: false,
 
             clear_color: Color::new(0.1, 0.1, 0.1, 1.),
+            clear_color_fade: ColorFade::new(
+                glam::vec4(0.1, 0.1, 0.1, 1.),
+                glam::vec4(0.1, 0.1, 0.1, 1.),
+                0.,
+            ),
             camera_data,
             camera,
+            camera_controller: CameraController::default(),
+            view_size,
+            frame_stats: FrameStats::default(),
+            depth_view,
+            labels: Vec::new(),
+
+            #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+            recorder: None,
+            #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+            screenshot_path: None,
         }
     }
 
-    pub fn resize(&mut self, size: Size<u32>) {
-        log::debug!("Resizing window with new size {}", size);
+    /// Resize the surface and camera. The camera works in logical pixels so the
+    /// world stays the same size on screens with a higher scale factor.
+    pub fn resize(&mut self, size: Size<u32>, scale_factor: f32) {
+        log::debug!(
+            "Resizing window with new size {} and scale factor {}",
+            size,
+            scale_factor
+        );
         self.config.width = size.width;
         self.config.height = size.height;
 
         self.surface.configure(&self.device, &self.config);
+        self.depth_view = create_depth_texture(&self.device, size.width, size.height);
+
+        self.view_size = logical_view_size(size, scale_factor);
+
+        self.update_camera();
+    }
+
+    #[inline]
+    pub fn camera_controller(&self) -> &CameraController {
+        &self.camera_controller
+    }
+
+    /// Changes made to the controller are applied on the next call to `update_camera_controller`
+    #[inline]
+    pub fn camera_controller_mut(&mut self) -> &mut CameraController {
+        &mut self.camera_controller
+    }
+
+    /// Apply mouse input to the camera controller and update the camera
+    pub fn update_camera_controller(
+        &mut self,
+        mouse_input: &MouseInput,
+        mouse_buttons: &Input<MouseButton>,
+    ) {
+        self.camera_controller
+            .update(mouse_input, mouse_buttons, self.world_per_pixel());
+        self.update_camera();
+    }
+
+    fn update_camera(&mut self) {
+        let half_size = self.camera_controller.half_extents(self.view_size);
 
         // self.camera_data
         //     .set_size(size.width as f32, size.height as f32);
-        self.camera_data
-            .set_size_centered(size.width as f32 / 2., size.height as f32 / 2.);
+        self.camera_data.set_size_centered(half_size.x, half_size.y);
+
+        self.camera.update_camera(
+            &self.queue,
+            &self.camera_data,
+            &glam::Affine3A::from_translation(self.camera_controller.pan.extend(0.)),
+        );
+    }
+
+    /// Start recording the given number of frames to a gif at `path`.
+    /// Any recording already in progress is encoded first.
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    pub fn start_recording(&mut self, frames: usize, path: impl Into<std::path::PathBuf>) {
+        self.stop_recording();
+        self.recorder = Some(FrameRecorder::new(frames, path));
+    }
+
+    /// Stop recording and encode the frames captured so far
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    pub fn stop_recording(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            if let Err(e) = recorder.encode() {
+                log::error!("Failed to encode recording: {}", e);
+            }
+        }
+    }
+
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    #[inline]
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    /// Add a captured frame to the current recording, stopping once it is full
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    pub fn record_frame(&mut self, frame: image::RgbaImage) {
+        let Some(recorder) = &mut self.recorder else {
+            return;
+        };
+
+        if recorder.push_frame(frame) {
+            self.stop_recording();
+        }
+    }
 
-        self.camera
-            .update_camera(&self.queue, &self.camera_data, &glam::Affine3A::IDENTITY);
+    /// Save the next captured frame as an image at `path`
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    #[inline]
+    pub fn request_screenshot(&mut self, path: impl Into<std::path::PathBuf>) {
+        self.screenshot_path = Some(path.into());
+    }
+
+    /// Save any requested screenshot and add the frame to the current recording.
+    /// Should be called once the frame has been prepped.
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    pub fn process_captures(&mut self) {
+        if self.screenshot_path.is_none() && self.recorder.is_none() {
+            return;
+        }
+
+        let Some(frame) = self.capture() else {
+            self.screenshot_path = None;
+            return;
+        };
+
+        if let Some(path) = self.screenshot_path.take() {
+            match frame.save(&path) {
+                Ok(()) => log::info!("Saved screenshot to '{}'", path.display()),
+                Err(e) => log::error!("Failed to save screenshot: {}", e),
+            }
+        }
+
+        self.record_frame(frame);
+    }
+
+    /// Render the prepped frame into an offscreen texture and copy it back to the cpu.
+    /// The surface texture can't always be copied from, so the scene is drawn again instead.
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    pub fn capture(&self) -> Option<image::RgbaImage> {
+        let swap_red_blue = match self.config.format {
+            wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+            format => {
+                log::warn!("Can't capture frames with surface format {:?}", format);
+                return None;
+            }
+        };
+
+        let texture = create_capture_texture(
+            &self.device,
+            self.config.format,
+            self.config.width,
+            self.config.height,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Capture Encoder"),
+            });
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Capture Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: self.clear_color.r,
+                            g: self.clear_color.g,
+                            b: self.clear_color.b,
+                            a: self.clear_color.a,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+
+            self.render_pipelines(&mut render_pass);
+        }
+
+        self.queue.submit(Some(encoder.finish()));
+
+        read_texture(&self.device, &self.queue, &texture, swap_red_blue)
+    }
+
+    /// How much darker the tail of each chain is drawn than its head, from 0 to 1.
+    /// Polygons keep their vertex colors at 0, the default.
+    #[inline]
+    pub fn gradient_strength(&self) -> f32 {
+        self.polygon_pipeline.gradient_strength()
+    }
+
+    #[inline]
+    pub fn set_gradient_strength(&mut self, strength: f32) {
+        self.polygon_pipeline
+            .set_gradient_strength(&self.queue, strength);
+    }
+
+    /// Toggle vsync by reconfiguring the surface's present mode.
+    /// The auto present modes fall back to the closest mode the surface supports.
+    pub fn set_vsync(&mut self, on: bool) {
+        self.config.present_mode = match on {
+            true => wgpu::PresentMode::AutoVsync,
+            false => wgpu::PresentMode::AutoNoVsync,
+        };
+
+        log::debug!("Setting present mode to {:?}", self.config.present_mode);
+        self.surface.configure(&self.device, &self.config);
+    }
+
+    #[inline]
+    pub fn vsync(&self) -> bool {
+        matches!(
+            self.config.present_mode,
+            wgpu::PresentMode::AutoVsync | wgpu::PresentMode::Fifo | wgpu::PresentMode::FifoRelaxed
+        )
+    }
+
+    /// How many world units a single physical pixel of the surface covers, ignoring camera zoom.
+    #[inline]
+    pub fn world_per_pixel(&self) -> f32 {
+        world_per_pixel(self.view_size, self.config.width)
     }
 
-    pub fn prep(&mut self) {
+    /// Convert a window position (winit 0,0 starts top left) to world space,
+    /// accounting for the camera's zoom and pan.
+    #[inline]
+    pub fn screen_to_world(&self, pos: glam::Vec2, window_size: Size<u32>) -> glam::Vec2 {
+        self.camera_controller()
+            .screen_to_world(pos, window_size, self.world_per_pixel())
+    }
+
+    /// Fade the clear color from its current value to the given color
+    pub fn fade_clear_color(&mut self, color: glam::Vec4) {
+        self.clear_color_fade = ColorFade::new(
+            self.clear_color_fade.current(),
+            color,
+            Self::CLEAR_COLOR_FADE_DURATION,
+        );
+    }
+
+    pub fn prep(&mut self, delta_seconds: f32) {
+        if !self.clear_color_fade.finished() {
+            let color = self.clear_color_fade.step(delta_seconds);
+            self.clear_color = Color::new(
+                color.x.into(),
+                color.y.into(),
+                color.z.into(),
+                color.w.into(),
+            );
+        }
+
+        // Labels keep the same size on screen regardless of zoom
+        let label_height = Self::LABEL_HEIGHT * self.camera_controller.zoom;
+        flush_labels(
+            &mut self.labels,
+            &mut self.line_pipeline.lines,
+            label_height,
+        );
+
+        // Hidden circles aren't drawn, so there's no need to upload them
+        if !self.render_circles {
+            self.circle_pipeline.clear_prep();
+        }
+
         self.circle_pipeline.finish_prep(&self.device, &self.queue);
-        self.polygon_pipeline.finish_prep();
+        self.polygon_pipeline.finish_prep(&self.device, &self.queue);
+        self.line_pipeline.finish_prep(&self.device, &self.queue);
+
+        self.frame_stats = FrameStats::collect(
+            &self.circle_pipeline.circles,
+            &self.polygon_pipeline.instances,
+        );
+    }
+
+    /// Draw a line of text with its bottom left corner at `pos` this frame.
+    /// Supports digits, `-`, `.` and `:`.
+    #[inline]
+    pub fn prep_label(&mut self, pos: glam::Vec2, text: impl Into<String>) {
+        self.labels.push((pos, text.into()));
+    }
+
+    /// Geometry prepared for the current frame
+    #[inline]
+    pub fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
     }
 
     pub fn render(&self) {
         let mut encoder = RenderEncoder::new(&self.device, &self.surface).unwrap();
 
         let mut render_pass = encoder.begin_render_pass(RenderPassDesc {
-            use_depth: None,
+            use_depth: Some(&self.depth_view),
             clear_color: Some(self.clear_color),
         });
 
+        self.render_pipelines(&mut render_pass);
+
+        render_pass.drop();
+        encoder.finish(&self.queue);
+    }
+
+    fn render_pipelines(&self, render_pass: &mut impl DrawPass) {
         if self.render_circles {
             self.circle_pipeline
-                .render(&mut render_pass, self.camera.bind_group());
+                .render(render_pass, self.camera.bind_group());
         }
 
         if self.render_polygons {
             self.polygon_pipeline
-                .render(&mut render_pass, self.camera.bind_group());
+                .render(render_pass, self.camera.bind_group());
         }
 
-        render_pass.drop();
-        encoder.finish(&self.queue);
+        if self.render_lines {
+            self.line_pipeline
+                .render(render_pass, self.camera.bind_group());
+        }
+    }
+}
+
+/// Pans and zooms the camera with the mouse.
+/// Scrolling zooms and dragging with the middle mouse button pans.
+pub struct CameraController {
+    /// Multiplier on the visible area, higher values zoom out
+    pub zoom: f32,
+    /// World position at the centre of the screen
+    pub pan: glam::Vec2,
+    /// Fraction the zoom changes per scroll step
+    pub zoom_speed: f32,
+    last_mouse_pos: glam::Vec2,
+}
+
+impl Default for CameraController {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            zoom: 1.,
+            pan: glam::Vec2::ZERO,
+            zoom_speed: 0.1,
+            last_mouse_pos: glam::Vec2::ZERO,
+        }
+    }
+}
+
+impl CameraController {
+    const MIN_ZOOM: f32 = 0.1;
+    const MAX_ZOOM: f32 = 10.;
+
+    /// Update zoom and pan from the mouse. Returns true if the camera moved.
+    pub fn update(
+        &mut self,
+        mouse_input: &MouseInput,
+        mouse_buttons: &Input<MouseButton>,
+        world_per_pixel: f32,
+    ) -> bool {
+        let mouse_pos = mouse_input.position();
+        let mouse_delta = mouse_pos - self.last_mouse_pos;
+        self.last_mouse_pos = mouse_pos;
+
+        let mut moved = false;
+
+        let scroll = mouse_input.scroll().y;
+        if scroll != 0. {
+            self.zoom =
+                (self.zoom * (1. - scroll * self.zoom_speed)).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+            moved = true;
+        }
+
+        // Winit y goes down the screen while world y goes up
+        if mouse_buttons.pressed(MouseButton::Middle) && mouse_delta != glam::Vec2::ZERO {
+            self.pan -= glam::vec2(mouse_delta.x, -mouse_delta.y) * world_per_pixel * self.zoom;
+            moved = true;
+        }
+
+        moved
+    }
+
+    /// Half the width and height of the world visible in a view of the given (unzoomed) size
+    #[inline]
+    pub fn half_extents(&self, view_size: glam::Vec2) -> glam::Vec2 {
+        view_size / 2. * self.zoom
+    }
+
+    /// Convert a position relative to the screen centre (in unzoomed world units) to world space
+    #[inline]
+    pub fn to_world(&self, pos: glam::Vec2) -> glam::Vec2 {
+        pos * self.zoom + self.pan
+    }
+
+    /// Convert a window position (winit 0,0 starts top left) to world space,
+    /// given how many unzoomed world units each pixel covers.
+    pub fn screen_to_world(
+        &self,
+        pos: glam::Vec2,
+        window_size: Size<u32>,
+        world_per_pixel: f32,
+    ) -> glam::Vec2 {
+        let window_size = glam::vec2(window_size.width as f32, window_size.height as f32);
+
+        // Flip y to point up and move (0, 0) to the screen centre
+        let centred = glam::vec2(pos.x, window_size.y - pos.y) - window_size / 2.;
+        self.to_world(centred * world_per_pixel)
+    }
+
+    #[inline]
+    pub fn reset(&mut self) {
+        self.zoom = 1.;
+        self.pan = glam::Vec2::ZERO;
+    }
+}
+
+/// Drawing operations used by substates, so they can be built and stepped without a gpu
+pub trait RenderContext {
+    fn new_polygon(&mut self, vertices: &[PolygonVertex], indices: &[u16]) -> PolygonInstance;
+
+    fn update_polygon(
+        &mut self,
+        instance: &mut PolygonInstance,
+        vertices: &[PolygonVertex],
+        indices: &[u16],
+    );
+
+    fn prep_circle(&mut self, circle: CircleInstance);
+
+    fn prep_line(&mut self, from: glam::Vec2, to: glam::Vec2, color: glam::Vec4);
+
+    /// Prep a line between each consecutive pair of points
+    fn prep_strip(&mut self, points: &[glam::Vec2], color: glam::Vec4) {
+        points
+            .windows(2)
+            .for_each(|pair| self.prep_line(pair[0], pair[1], color));
+    }
+}
+
+impl RenderContext for Renderer {
+    #[inline]
+    fn new_polygon(&mut self, vertices: &[PolygonVertex], indices: &[u16]) -> PolygonInstance {
+        self.polygon_pipeline
+            .new_polygon(&self.device, vertices, indices)
+    }
+
+    #[inline]
+    fn update_polygon(
+        &mut self,
+        instance: &mut PolygonInstance,
+        vertices: &[PolygonVertex],
+        indices: &[u16],
+    ) {
+        instance.update(&self.device, &self.queue, vertices, indices);
+    }
+
+    #[inline]
+    fn prep_circle(&mut self, circle: CircleInstance) {
+        self.circle_pipeline.prep_circle(circle);
+    }
+
+    #[inline]
+    fn prep_line(&mut self, from: glam::Vec2, to: glam::Vec2, color: glam::Vec4) {
+        self.line_pipeline.prep_line(from, to, color);
+    }
+}
+
+/// Render context that draws nothing, only counting what it is given
+#[derive(Default)]
+pub struct NullRenderer {
+    pub polygons: usize,
+    pub polygon_updates: usize,
+    pub circles: usize,
+    pub lines: usize,
+}
+
+impl RenderContext for NullRenderer {
+    #[inline]
+    fn new_polygon(&mut self, vertices: &[PolygonVertex], indices: &[u16]) -> PolygonInstance {
+        self.polygons += 1;
+        PolygonInstance::headless(vertices, indices)
+    }
+
+    #[inline]
+    fn update_polygon(
+        &mut self,
+        instance: &mut PolygonInstance,
+        vertices: &[PolygonVertex],
+        indices: &[u16],
+    ) {
+        self.polygon_updates += 1;
+        instance.update_headless(vertices, indices);
+    }
+
+    #[inline]
+    fn prep_circle(&mut self, _circle: CircleInstance) {
+        self.circles += 1;
+    }
+
+    #[inline]
+    fn prep_line(&mut self, _from: glam::Vec2, _to: glam::Vec2, _color: glam::Vec4) {
+        self.lines += 1;
     }
 }
 
@@ -153,16 +734,20 @@ pub struct CircleInstance {
     border_radius: f32,
     color: glam::Vec4,
     border_color: glam::Vec4,
+    arc_start: f32,
+    arc_end: f32,
+    pad: [u32; 2],
 }
 
 impl Vertex for CircleInstance {
     fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        const VERTEX_ATTRIBUTES: [wgpu::VertexAttribute; 6] = wgpu::vertex_attr_array![
             1 => Float32x2,
             2 => Float32,
             3 => Float32,
             4 => Float32x4,
             5 => Float32x4,
+            6 => Float32x2,
         ];
 
         wgpu::VertexBufferLayout {
@@ -181,12 +766,20 @@ impl CircleInstance {
             border_radius: 6.,
             color: glam::Vec4::ONE,
             border_color: glam::vec4(0., 0., 0., 1.),
+            arc_start: 0.,
+            arc_end: TAU,
+            pad: [0; 2],
         }
     }
     pub fn with_color(mut self, color: glam::Vec4) -> Self {
         self.color = color;
         self
     }
+    /// Color inside the border. Same as `with_color`, kept alongside `with_border` for clarity
+    #[inline]
+    pub fn with_fill(self, color: glam::Vec4) -> Self {
+        self.with_color(color)
+    }
     pub fn hollow(mut self) -> Self {
         self.color = glam::Vec4::ZERO;
         self
@@ -196,57 +789,352 @@ impl CircleInstance {
         self.border_color = color;
         self
     }
+    /// Only draw the part of the circle between the two angles, going anticlockwise from `start`
+    pub fn with_arc(mut self, start: f32, end: f32) -> Self {
+        self.arc_start = start;
+        self.arc_end = end;
+        self
+    }
+    #[cfg(test)]
+    #[inline]
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+    #[cfg(test)]
+    #[inline]
+    pub fn color(&self) -> glam::Vec4 {
+        self.color
+    }
+    #[inline]
+    pub fn arc(&self) -> (f32, f32) {
+        (self.arc_start, self.arc_end)
+    }
+    #[inline]
+    pub fn is_full_circle(&self) -> bool {
+        self.arc_end - self.arc_start >= TAU
+    }
 }
 
-pub struct CirclePipeline {
-    pipeline: wgpu::RenderPipeline,
+/// Turn every queued label into lines, leaving the queue empty for the next frame
+fn flush_labels(labels: &mut Vec<(glam::Vec2, String)>, lines: &mut LineQueue, height: f32) {
+    labels.drain(..).for_each(|(pos, text)| {
+        labels::text_segments(&text, pos, height)
+            .into_iter()
+            .for_each(|(from, to)| lines.prep_line(from, to, Renderer::LABEL_COLOR))
+    });
+}
 
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    index_count: u32,
+/// Format of the depth buffer used to order polygons by their nodes' z
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Create a depth texture matching the surface size, returning its view
+pub fn create_depth_texture(device: &Device, width: u32, height: u32) -> wgpu::TextureView {
+    device
+        .create_texture(&wgpu::TextureDescriptor {
+            label: Some("Depth Texture"),
+            size: texture_size(width, height),
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: DEPTH_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+        .create_view(&wgpu::TextureViewDescriptor::default())
+}
 
-    instance_buffer: wgpu::Buffer,
-    instance_count: u32,
+/// Size of a 2d texture, kept to at least a pixel each way so a minimised window still gets one
+#[inline]
+fn texture_size(width: u32, height: u32) -> wgpu::Extent3d {
+    wgpu::Extent3d {
+        width: width.max(1),
+        height: height.max(1),
+        depth_or_array_layers: 1,
+    }
+}
 
-    to_prep: Vec<CircleInstance>,
+/// Depth state for pipelines that ignore depth and draw over everything in submission order
+fn overlay_depth_state() -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: DEPTH_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil: wgpu::StencilState::default(),
+        bias: wgpu::DepthBiasState::default(),
+    }
 }
 
-impl CirclePipeline {
-    pub fn new(device: &Device, config: &SurfaceConfig, shared: &SharedRenderResources) -> Self {
-        let pipeline = tools::create_pipeline(
-            device,
-            config,
-            "Circle Pipeline",
-            &[shared.camera_bind_group_layout()],
-            &[RawVertex::desc(), CircleInstance::desc()],
-            include_str!("circle_shader.wgsl").into(),
-            tools::RenderPipelineDescriptor::default(),
-        );
+/// Create an offscreen texture that can be rendered to and copied back with [`read_texture`]
+#[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+fn create_capture_texture(
+    device: &Device,
+    format: wgpu::TextureFormat,
+    width: u32,
+    height: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Capture Texture"),
+        size: texture_size(width, height),
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    })
+}
 
-        let vertex_buffer = tools::create_buffer(
-            device,
-            tools::BufferType::Vertex,
-            "Circle Pipeline",
-            &RECT_VERTICES,
-        );
+/// Bytes per row of a 4 byte per pixel copy buffer, as rows must be padded to the copy alignment
+#[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+#[inline]
+fn padded_row_bytes(width: u32) -> u32 {
+    (width * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT) * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+}
 
-        let index_buffer = tools::create_buffer(
-            device,
-            tools::BufferType::Index,
-            "Circle Pipeline",
-            &RECT_INDICES,
-        );
+/// Strip the padding from the end of each row of a copy buffer holding 4 byte pixels
+#[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+fn unpad_rows(data: &[u8], width: u32) -> Vec<u8> {
+    data.chunks(padded_row_bytes(width) as usize)
+        .flat_map(|row| &row[..width as usize * 4])
+        .copied()
+        .collect()
+}
 
-        let index_count = RECT_INDICES.len() as u32;
+/// Copy a 4 byte per pixel texture back to the cpu, swapping to rgba order if needed
+#[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+fn read_texture(
+    device: &Device,
+    queue: &Queue,
+    texture: &wgpu::Texture,
+    swap_red_blue: bool,
+) -> Option<image::RgbaImage> {
+    let size = texture.size();
+
+    let padded_row_bytes = padded_row_bytes(size.width);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capture Buffer"),
+        size: padded_row_bytes as u64 * size.height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Capture Copy Encoder"),
+    });
+
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_row_bytes),
+                rows_per_image: Some(size.height),
+            },
+        },
+        size,
+    );
+
+    queue.submit(Some(encoder.finish()));
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let slice = buffer.slice(..);
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+
+    if let Err(e) = receiver
+        .recv()
+        .map_err(|e| e.to_string())
+        .and_then(|result| result.map_err(|e| e.to_string()))
+    {
+        log::error!("Failed to read captured frame: {}", e);
+        return None;
+    }
 
-        let instance_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Circle Pipeline Instance Buffer"),
-            size: 0,
-            usage: wgpu::BufferUsages::VERTEX,
-            mapped_at_creation: false,
-        });
+    let mut pixels = unpad_rows(&slice.get_mapped_range(), size.width);
+    buffer.unmap();
 
-        let instance_count = 0;
+    if swap_red_blue {
+        pixels
+            .chunks_exact_mut(4)
+            .for_each(|pixel| pixel.swap(0, 2));
+    }
+
+    image::RgbaImage::from_raw(size.width, size.height, pixels)
+}
+
+/// Draw calls recorded by the pipelines. Implemented for the roots_core pass that draws to the
+/// window and the wgpu pass that draws captures offscreen, so both draw the same scene.
+pub trait DrawPass {
+    fn use_pipeline(&mut self, pipeline: &wgpu::RenderPipeline);
+    fn use_bind_group(&mut self, index: u32, bind_group: &wgpu::BindGroup);
+    fn use_vertex_buffer(&mut self, slot: u32, buffer: wgpu::BufferSlice<'_>);
+    /// Indices are always `u16`
+    fn use_index_buffer(&mut self, buffer: wgpu::BufferSlice<'_>);
+    fn draw_elements(&mut self, indices: Range<u32>, instances: Range<u32>);
+    fn draw_vertices(&mut self, vertices: Range<u32>, instances: Range<u32>);
+}
+
+macro_rules! impl_draw_pass {
+    ($pass:ty) => {
+        impl DrawPass for $pass {
+            #[inline]
+            fn use_pipeline(&mut self, pipeline: &wgpu::RenderPipeline) {
+                self.set_pipeline(pipeline);
+            }
+
+            #[inline]
+            fn use_bind_group(&mut self, index: u32, bind_group: &wgpu::BindGroup) {
+                self.set_bind_group(index, bind_group, &[]);
+            }
+
+            #[inline]
+            fn use_vertex_buffer(&mut self, slot: u32, buffer: wgpu::BufferSlice<'_>) {
+                self.set_vertex_buffer(slot, buffer);
+            }
+
+            #[inline]
+            fn use_index_buffer(&mut self, buffer: wgpu::BufferSlice<'_>) {
+                self.set_index_buffer(buffer, wgpu::IndexFormat::Uint16);
+            }
+
+            #[inline]
+            fn draw_elements(&mut self, indices: Range<u32>, instances: Range<u32>) {
+                self.draw_indexed(indices, 0, instances);
+            }
+
+            #[inline]
+            fn draw_vertices(&mut self, vertices: Range<u32>, instances: Range<u32>) {
+                self.draw(vertices, instances);
+            }
+        }
+    };
+}
+
+impl_draw_pass!(RenderPass<'_>);
+impl_draw_pass!(wgpu::RenderPass<'_>);
+
+/// Circles queued on the cpu for the next upload
+pub struct CircleQueue {
+    to_prep: Vec<CircleInstance>,
+    /// Number of circles uploaded by the last `finish`
+    instance_count: u32,
+    /// Number of circles the instance buffer can hold before it needs to grow
+    instance_capacity: u32,
+}
+
+impl CircleQueue {
+    fn new(instance_capacity: u32) -> Self {
+        Self {
+            to_prep: Vec::new(),
+            instance_count: 0,
+            instance_capacity,
+        }
+    }
+
+    #[inline]
+    pub fn prep_circle(&mut self, circle: CircleInstance) {
+        self.to_prep.push(circle);
+    }
+
+    /// Number of circles waiting to be uploaded this frame.
+    #[inline]
+    pub fn pending_count(&self) -> usize {
+        self.to_prep.len()
+    }
+
+    /// Discard all circles queued so far this frame.
+    #[inline]
+    pub fn clear_prep(&mut self) {
+        self.to_prep.clear();
+    }
+
+    /// Number of circles uploaded by the last frame
+    #[inline]
+    pub fn instance_count(&self) -> u32 {
+        self.instance_count
+    }
+
+    /// Number of circles the instance buffer can hold without reallocating
+    #[inline]
+    pub fn instance_capacity(&self) -> u32 {
+        self.instance_capacity
+    }
+
+    /// Double the capacity until this frame's circles fit. The capacity never shrinks.
+    /// Returns true if the instance buffer has to be recreated.
+    fn reserve(&mut self) -> bool {
+        let count = self.pending_count() as u32;
+        if count <= self.instance_capacity {
+            return false;
+        }
+
+        while self.instance_capacity < count {
+            self.instance_capacity *= 2;
+        }
+        true
+    }
+
+    /// Count this frame's circles as uploaded and start queuing the next frame
+    fn finish(&mut self) {
+        self.instance_count = self.pending_count() as u32;
+        self.clear_prep();
+    }
+}
+
+pub struct CirclePipeline {
+    pipeline: wgpu::RenderPipeline,
+
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
+
+    instance_buffer: wgpu::Buffer,
+    circles: CircleQueue,
+}
+
+impl CirclePipeline {
+    const MIN_INSTANCE_CAPACITY: u32 = 64;
+
+    pub fn new(device: &Device, config: &SurfaceConfig, shared: &SharedRenderResources) -> Self {
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Circle Pipeline",
+            &[shared.camera_bind_group_layout()],
+            &[RawVertex::desc(), CircleInstance::desc()],
+            include_str!("circle_shader.wgsl").into(),
+            tools::RenderPipelineDescriptor {
+                depth_stencil: Some(overlay_depth_state()),
+                ..Default::default()
+            },
+        );
+
+        let vertex_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Vertex,
+            "Circle Pipeline",
+            &RECT_VERTICES,
+        );
+
+        let index_buffer = tools::create_buffer(
+            device,
+            tools::BufferType::Index,
+            "Circle Pipeline",
+            &RECT_INDICES,
+        );
+
+        let index_count = RECT_INDICES.len() as u32;
+
+        let instance_buffer = Self::create_instance_buffer(device, Self::MIN_INSTANCE_CAPACITY);
 
         Self {
             pipeline,
@@ -254,14 +1142,152 @@ impl CirclePipeline {
             index_buffer,
             index_count,
             instance_buffer,
-            instance_count,
-            to_prep: Vec::new(),
+            circles: CircleQueue::new(Self::MIN_INSTANCE_CAPACITY),
         }
     }
 
+    fn create_instance_buffer(device: &Device, capacity: u32) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Circle Pipeline Instance Buffer"),
+            size: capacity as u64 * std::mem::size_of::<CircleInstance>() as u64,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
     #[inline]
     pub fn prep_circle(&mut self, circle: CircleInstance) {
-        self.to_prep.push(circle);
+        self.circles.prep_circle(circle);
+    }
+
+    /// Discard all circles queued so far this frame.
+    #[inline]
+    pub fn clear_prep(&mut self) {
+        self.circles.clear_prep();
+    }
+
+    /// Upload this frame's circles, doubling the instance buffer when it runs out of room.
+    /// The buffer never shrinks, so only the used range is written each frame.
+    pub fn finish_prep(&mut self, device: &Device, queue: &Queue) {
+        if self.circles.reserve() {
+            self.instance_buffer =
+                Self::create_instance_buffer(device, self.circles.instance_capacity());
+        }
+
+        if self.circles.pending_count() > 0 {
+            queue.write_buffer(
+                &self.instance_buffer,
+                0,
+                bytemuck::cast_slice(&self.circles.to_prep),
+            );
+        }
+
+        self.circles.finish();
+    }
+
+    pub fn render(&self, pass: &mut impl DrawPass, camera_bind_group: &wgpu::BindGroup) {
+        let instance_count = self.circles.instance_count();
+        if instance_count == 0 {
+            return;
+        }
+
+        pass.use_pipeline(&self.pipeline);
+        pass.use_bind_group(0, camera_bind_group);
+
+        pass.use_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.use_index_buffer(self.index_buffer.slice(..));
+        let used = instance_count as u64 * std::mem::size_of::<CircleInstance>() as u64;
+        pass.use_vertex_buffer(1, self.instance_buffer.slice(..used));
+
+        pass.draw_elements(0..self.index_count, 0..instance_count);
+    }
+}
+
+/// Line segments queued on the cpu for the next upload, as two vertices each
+#[derive(Default)]
+pub struct LineQueue {
+    to_prep: Vec<PolygonVertex>,
+}
+
+impl LineQueue {
+    #[inline]
+    pub fn prep_line(&mut self, from: glam::Vec2, to: glam::Vec2, color: glam::Vec4) {
+        self.to_prep.extend([from, to].map(|pos| PolygonVertex {
+            pos,
+            depth: 0.,
+            t: 0.,
+            color,
+        }));
+    }
+
+    /// Prep a line between each consecutive pair of points
+    pub fn prep_strip(&mut self, points: &[glam::Vec2], color: glam::Vec4) {
+        points
+            .windows(2)
+            .for_each(|pair| self.prep_line(pair[0], pair[1], color));
+    }
+}
+
+/// Draws batches of single color line segments, reusing the polygon vertex format and shader
+pub struct LinePipeline {
+    pipeline: wgpu::RenderPipeline,
+    /// Lines have no gradient, but share the polygon shader that reads it
+    gradient: GradientUniform,
+
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+
+    lines: LineQueue,
+}
+
+impl LinePipeline {
+    pub fn new(device: &Device, config: &SurfaceConfig, shared: &SharedRenderResources) -> Self {
+        let gradient = GradientUniform::new(device, 0.);
+
+        let pipeline = tools::create_pipeline(
+            device,
+            config,
+            "Line Pipeline",
+            &[
+                shared.camera_bind_group_layout(),
+                &gradient.bind_group_layout,
+            ],
+            &[PolygonVertex::desc()],
+            include_str!("polygon_shader.wgsl").into(),
+            tools::RenderPipelineDescriptor {
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::LineList,
+                    ..Default::default()
+                },
+                depth_stencil: Some(overlay_depth_state()),
+                ..Default::default()
+            },
+        );
+
+        let vertex_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Line Pipeline Vertex Buffer"),
+            size: 0,
+            usage: wgpu::BufferUsages::VERTEX,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            pipeline,
+            gradient,
+            vertex_buffer,
+            vertex_count: 0,
+            lines: LineQueue::default(),
+        }
+    }
+
+    #[inline]
+    pub fn prep_line(&mut self, from: glam::Vec2, to: glam::Vec2, color: glam::Vec4) {
+        self.lines.prep_line(from, to, color);
+    }
+
+    #[inline]
+    pub fn prep_strip(&mut self, points: &[glam::Vec2], color: glam::Vec4) {
+        self.lines.prep_strip(points, color);
     }
 
     #[inline]
@@ -269,29 +1295,89 @@ impl CirclePipeline {
         tools::update_buffer_data(
             device,
             queue,
-            tools::BufferType::Instance,
-            "Cirle Pipeline",
-            &mut self.instance_buffer,
-            &mut self.instance_count,
-            &self.to_prep,
+            tools::BufferType::VertexDynamic,
+            "Line Pipeline",
+            &mut self.vertex_buffer,
+            &mut self.vertex_count,
+            &self.lines.to_prep,
         );
 
-        self.to_prep.clear();
+        self.lines.to_prep.clear();
     }
 
-    pub fn render(&self, pass: &mut RenderPass, camera_bind_group: &wgpu::BindGroup) {
-        if self.instance_count == 0 {
+    pub fn render(&self, pass: &mut impl DrawPass, camera_bind_group: &wgpu::BindGroup) {
+        if self.vertex_count == 0 {
             return;
         }
 
-        pass.set_pipeline(&self.pipeline);
-        pass.set_bind_group(0, camera_bind_group, &[]);
+        pass.use_pipeline(&self.pipeline);
+        pass.use_bind_group(0, camera_bind_group);
+        pass.use_bind_group(1, &self.gradient.bind_group);
+        pass.use_vertex_buffer(0, self.vertex_buffer.slice(..));
+        pass.draw_vertices(0..self.vertex_count, 0..1);
+    }
+}
 
-        pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-        pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+/// Uniform read by the polygon shader for how much darker the tail of a chain is than its head.
+/// A strength of 0 keeps polygons at their vertex colors.
+struct GradientUniform {
+    buffer: wgpu::Buffer,
+    bind_group_layout: wgpu::BindGroupLayout,
+    bind_group: wgpu::BindGroup,
+    strength: f32,
+}
 
-        pass.draw_indexed(0..self.index_count, 0, 0..self.instance_count);
+impl GradientUniform {
+    fn new(device: &Device, strength: f32) -> Self {
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Gradient Uniform"),
+            // Padded to the minimum uniform size
+            size: std::mem::size_of::<[f32; 4]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: true,
+        });
+        buffer
+            .slice(..)
+            .get_mapped_range_mut()
+            .copy_from_slice(bytemuck::bytes_of(&[strength, 0., 0., 0.]));
+        buffer.unmap();
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Gradient Bind Group Layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Gradient Bind Group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+            strength,
+        }
+    }
+
+    fn set_strength(&mut self, queue: &Queue, strength: f32) {
+        if self.strength != strength {
+            self.strength = strength;
+            queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&strength));
+        }
     }
 }
 
@@ -299,7 +1385,11 @@ impl CirclePipeline {
 #[derive(bytemuck::Pod, bytemuck::Zeroable, Clone, Copy, Debug)]
 pub struct PolygonVertex {
     pub pos: glam::Vec2,
-    pub pad: [u32; 2],
+    /// Higher values are drawn in front. See `Node::z`
+    pub depth: f32,
+    /// Gradient coordinate along a chain, from 0 at the head to 1 at the tail.
+    /// The polygon shader shades each pixel by it.
+    pub t: f32,
     pub color: glam::Vec4,
 }
 
@@ -322,13 +1412,39 @@ impl Vertex for PolygonVertex {
 pub struct PolygonInstance(Rc<RefCell<PolygonInstanceInner>>);
 
 pub struct PolygonInstanceInner {
-    vertex_buffer: wgpu::Buffer,
+    buffers: Option<PolygonBuffers>,
     vertex_count: u32,
-    index_buffer: wgpu::Buffer,
     index_count: u32,
+
+    // Copy of the geometry used to build the batched buffers
+    vertices: Vec<PolygonVertex>,
+    indices: Vec<u16>,
+    dirty: bool,
+
+    visible: bool,
+    layer: i32,
+}
+
+struct PolygonBuffers {
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
 }
 
 impl PolygonInstance {
+    /// Create an instance with no gpu buffers that only tracks its vertex and index counts
+    pub fn headless(vertices: &[PolygonVertex], indices: &[u16]) -> Self {
+        Self(Rc::new(RefCell::new(PolygonInstanceInner {
+            buffers: None,
+            vertex_count: vertices.len() as u32,
+            index_count: indices.len() as u32,
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+            dirty: true,
+            visible: true,
+            layer: 0,
+        })))
+    }
+
     pub fn update(
         &mut self,
         device: &Device,
@@ -339,12 +1455,31 @@ impl PolygonInstance {
         let mut inner = self.0.borrow_mut();
 
         let PolygonInstanceInner {
-            vertex_buffer,
+            buffers,
             vertex_count,
-            index_buffer,
             index_count,
+            vertices: stored_vertices,
+            indices: stored_indices,
+            dirty,
+            ..
         } = inner.deref_mut();
 
+        stored_vertices.clear();
+        stored_vertices.extend_from_slice(vertices);
+        stored_indices.clear();
+        stored_indices.extend_from_slice(indices);
+        *dirty = true;
+
+        let Some(PolygonBuffers {
+            vertex_buffer,
+            index_buffer,
+        }) = buffers
+        else {
+            *vertex_count = vertices.len() as u32;
+            *index_count = indices.len() as u32;
+            return;
+        };
+
         tools::update_buffer_data(
             device,
             queue,
@@ -365,28 +1500,178 @@ impl PolygonInstance {
             indices,
         );
     }
+
+    /// Update the instance without any gpu buffers
+    fn update_headless(&mut self, vertices: &[PolygonVertex], indices: &[u16]) {
+        let mut inner = self.0.borrow_mut();
+        inner.vertex_count = vertices.len() as u32;
+        inner.index_count = indices.len() as u32;
+        inner.vertices = vertices.to_vec();
+        inner.indices = indices.to_vec();
+        inner.dirty = true;
+    }
+
+    /// Hide or show the instance without dropping its buffers
+    pub fn set_visible(&mut self, visible: bool) {
+        let mut inner = self.0.borrow_mut();
+        if inner.visible != visible {
+            inner.visible = visible;
+            inner.dirty = true;
+        }
+    }
+
+    #[inline]
+    pub fn is_visible(&self) -> bool {
+        self.0.borrow().visible
+    }
+
+    /// Set the draw order of the instance. Higher layers draw on top,
+    /// instances on the same layer draw in the order they were created.
+    pub fn set_layer(&mut self, layer: i32) {
+        let mut inner = self.0.borrow_mut();
+        if inner.layer != layer {
+            inner.layer = layer;
+            inner.dirty = true;
+        }
+    }
+
+    #[inline]
+    pub fn layer(&self) -> i32 {
+        self.0.borrow().layer
+    }
+}
+
+/// Polygon instances in draw order, kept while any handle to them is alive
+#[derive(Default)]
+pub struct PolygonInstances(Vec<PolygonInstance>);
+
+impl PolygonInstances {
+    #[inline]
+    pub fn push(&mut self, instance: PolygonInstance) {
+        self.0.push(instance);
+    }
+
+    /// Remove all instances with only one reference, returning true if any were removed
+    fn remove_dropped(&mut self) -> bool {
+        let count = self.0.len();
+        self.0.retain(|instance| Rc::strong_count(&instance.0) > 1);
+        count != self.0.len()
+    }
+
+    /// Sort the instances into draw order, returning true if the order changed.
+    /// The sort is stable, so instances keep their creation order within each layer.
+    fn sort_by_layer(&mut self) -> bool {
+        if self.0.is_sorted_by_key(|instance| instance.layer()) {
+            return false;
+        }
+
+        self.0.sort_by_key(|instance| instance.layer());
+        true
+    }
+
+    /// Clear the dirty flag of every instance, returning true if any were set
+    fn take_dirty(&self) -> bool {
+        self.0.iter().fold(false, |dirty, instance| {
+            std::mem::take(&mut instance.0.borrow_mut().dirty) | dirty
+        })
+    }
+
+    /// Number of instances that will be drawn
+    pub fn visible_instance_count(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|instance| instance.is_visible())
+            .count()
+    }
+
+    /// Total number of vertices and indices across all instances
+    pub fn geometry_count(&self) -> (u32, u32) {
+        self.0.iter().fold((0, 0), |(vertices, indices), instance| {
+            let instance = instance.0.borrow();
+            (
+                vertices + instance.vertex_count,
+                indices + instance.index_count,
+            )
+        })
+    }
+
+    /// Concatenate the geometry of all visible instances in draw order, offsetting indices to match.
+    /// Returns None if there are too many vertices to address with u16 indices.
+    fn combine(&self) -> Option<(Vec<PolygonVertex>, Vec<u16>)> {
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for instance in &self.0 {
+            let instance = instance.0.borrow();
+            if !instance.visible {
+                continue;
+            }
+
+            let offset = u16::try_from(vertices.len()).ok()?;
+            u16::try_from(vertices.len() + instance.vertices.len()).ok()?;
+
+            vertices.extend_from_slice(&instance.vertices);
+            indices.extend(instance.indices.iter().map(|index| index + offset));
+        }
+
+        Some((vertices, indices))
+    }
 }
 
 pub struct PolygonPipeline {
     pipeline: wgpu::RenderPipeline,
-    instances: Vec<PolygonInstance>,
+    gradient: GradientUniform,
+    instances: PolygonInstances,
+
+    /// Draw all instances with a single combined buffer instead of one draw call each.
+    /// On by default, instances are drawn separately when this is off.
+    pub batched: bool,
+    batch: Option<PolygonBatch>,
+    instances_changed: bool,
+}
+
+/// All polygon instances combined into one set of buffers
+struct PolygonBatch {
+    vertex_buffer: wgpu::Buffer,
+    vertex_count: u32,
+    index_buffer: wgpu::Buffer,
+    index_count: u32,
 }
 
 impl PolygonPipeline {
     pub fn new(device: &Device, config: &SurfaceConfig, shared: &SharedRenderResources) -> Self {
+        let gradient = GradientUniform::new(device, 0.);
+
         let pipeline = tools::create_pipeline(
             device,
             config,
             "Polygon Pipeline",
-            &[shared.camera_bind_group_layout()],
+            &[
+                shared.camera_bind_group_layout(),
+                &gradient.bind_group_layout,
+            ],
             &[PolygonVertex::desc()],
             include_str!("polygon_shader.wgsl").into(),
-            tools::RenderPipelineDescriptor::default(),
+            tools::RenderPipelineDescriptor {
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    // Equal depths keep their draw order so layers still apply
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                ..Default::default()
+            },
         );
 
         Self {
             pipeline,
-            instances: Vec::new(),
+            gradient,
+            instances: PolygonInstances::default(),
+            batched: true,
+            batch: None,
+            instances_changed: false,
         }
     }
 
@@ -406,37 +1691,712 @@ impl PolygonPipeline {
             tools::create_buffer(device, tools::BufferType::IndexDynamic, "Polygon", indices);
 
         let instance = PolygonInstance(Rc::new(RefCell::new(PolygonInstanceInner {
-            vertex_buffer,
+            buffers: Some(PolygonBuffers {
+                vertex_buffer,
+                index_buffer,
+            }),
             vertex_count: vertices.len() as u32,
-            index_buffer,
             index_count: indices.len() as u32,
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+            dirty: true,
+            visible: true,
+            layer: 0,
         })));
 
         self.instances.push(instance.clone());
+        self.instances_changed = true;
 
         instance
     }
 
-    pub fn finish_prep(&mut self) {
-        // Remove all instances with only one reference
-        self.instances
-            .retain(|instance| Rc::strong_count(&instance.0) > 1);
+    pub fn finish_prep(&mut self, device: &Device, queue: &Queue) {
+        self.instances_changed |= self.instances.remove_dropped();
+        self.instances_changed |= self.instances.sort_by_layer();
+
+        if !self.batched {
+            self.batch = None;
+            return;
+        }
+
+        // Only rebuild the combined buffers if something changed
+        let dirty = self.instances.take_dirty();
+        if !dirty && !self.instances_changed && self.batch.is_some() {
+            return;
+        }
+        self.instances_changed = false;
+
+        let Some((vertices, indices)) = self.instances.combine() else {
+            log::warn!("Too many polygon vertices to batch, drawing polygons individually");
+            self.batch = None;
+            return;
+        };
+
+        if indices.is_empty() {
+            self.batch = None;
+            return;
+        }
+
+        match &mut self.batch {
+            Some(batch) => {
+                tools::update_buffer_data(
+                    device,
+                    queue,
+                    tools::BufferType::VertexDynamic,
+                    "Polygon Batch",
+                    &mut batch.vertex_buffer,
+                    &mut batch.vertex_count,
+                    &vertices,
+                );
+                tools::update_buffer_data(
+                    device,
+                    queue,
+                    tools::BufferType::IndexDynamic,
+                    "Polygon Batch",
+                    &mut batch.index_buffer,
+                    &mut batch.index_count,
+                    &indices,
+                );
+            }
+            None => {
+                self.batch = Some(PolygonBatch {
+                    vertex_buffer: tools::create_buffer(
+                        device,
+                        tools::BufferType::VertexDynamic,
+                        "Polygon Batch",
+                        &vertices,
+                    ),
+                    vertex_count: vertices.len() as u32,
+                    index_buffer: tools::create_buffer(
+                        device,
+                        tools::BufferType::IndexDynamic,
+                        "Polygon Batch",
+                        &indices,
+                    ),
+                    index_count: indices.len() as u32,
+                })
+            }
+        }
     }
 
-    pub fn render(&self, pass: &mut RenderPass, camera_bind_group: &wgpu::BindGroup) {
-        if self.instances.len() == 0 {
+    /// How much darker the tail of each chain is drawn than its head, from 0 to 1
+    #[inline]
+    pub fn gradient_strength(&self) -> f32 {
+        self.gradient.strength
+    }
+
+    #[inline]
+    pub fn set_gradient_strength(&mut self, queue: &Queue, strength: f32) {
+        self.gradient.set_strength(queue, strength.clamp(0., 1.));
+    }
+
+    pub fn render(&self, pass: &mut impl DrawPass, camera_bind_group: &wgpu::BindGroup) {
+        if self.instances.0.is_empty() {
             return;
         }
 
-        pass.set_pipeline(&self.pipeline);
-        pass.set_bind_group(0, camera_bind_group, &[]);
+        pass.use_pipeline(&self.pipeline);
+        pass.use_bind_group(0, camera_bind_group);
+        pass.use_bind_group(1, &self.gradient.bind_group);
 
-        self.instances.iter().for_each(|instance| {
+        if let Some(batch) = &self.batch {
+            pass.use_vertex_buffer(0, batch.vertex_buffer.slice(..));
+            pass.use_index_buffer(batch.index_buffer.slice(..));
+            pass.draw_elements(0..batch.index_count, 0..1);
+            return;
+        }
+
+        self.instances.0.iter().for_each(|instance| {
             let instance = instance.0.borrow();
+            if !instance.visible {
+                return;
+            }
+            let Some(buffers) = &instance.buffers else {
+                return;
+            };
+
+            pass.use_vertex_buffer(0, buffers.vertex_buffer.slice(..));
+            pass.use_index_buffer(buffers.index_buffer.slice(..));
+            pass.draw_elements(0..instance.index_count, 0..1);
+        });
+    }
+}
+
+/// Size of the view in world units for a window, using logical pixels so
+/// the world stays the same size on screens with a higher scale factor
+#[inline]
+fn logical_view_size(size: Size<u32>, scale_factor: f32) -> glam::Vec2 {
+    glam::vec2(size.width as f32, size.height as f32) / scale_factor.max(f32::EPSILON)
+}
+
+/// How many world units a physical pixel covers when a view is stretched across a surface
+#[inline]
+fn world_per_pixel(view_size: glam::Vec2, surface_width: u32) -> f32 {
+    view_size.x / surface_width.max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cleared_circles_are_not_uploaded() {
+        let mut circles = CircleQueue::new(CirclePipeline::MIN_INSTANCE_CAPACITY);
+
+        (0..3).for_each(|index| circles.prep_circle(CircleInstance::new([index as f32, 0.], 5.)));
+        assert_eq!(circles.pending_count(), 3);
+
+        circles.clear_prep();
+        assert_eq!(circles.pending_count(), 0);
+
+        circles.finish();
+        assert_eq!(circles.instance_count(), 0);
+    }
+
+    #[test]
+    fn circle_capacity_grows_but_never_shrinks() {
+        let mut circles = CircleQueue::new(CirclePipeline::MIN_INSTANCE_CAPACITY);
+
+        let mut prep = |count: usize| {
+            (0..count)
+                .for_each(|index| circles.prep_circle(CircleInstance::new([index as f32, 0.], 5.)));
+            let grew = circles.reserve();
+            circles.finish();
+            (grew, circles.instance_capacity(), circles.instance_count())
+        };
+
+        let (grew, grown, count) = prep(100);
+        assert!(grew);
+        assert!(grown >= 100);
+        assert_eq!(count, 100);
+
+        let (grew, capacity, count) = prep(50);
+        assert!(!grew);
+        assert_eq!(capacity, grown);
+        assert_eq!(count, 50);
+    }
 
-            pass.set_vertex_buffer(0, instance.vertex_buffer.slice(..));
-            pass.set_index_buffer(instance.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            pass.draw_indexed(0..instance.index_count, 0, 0..1);
+    #[test]
+    fn three_lines_make_six_vertices() {
+        let mut lines = LineQueue::default();
+
+        lines.prep_strip(
+            &[
+                glam::vec2(0., 0.),
+                glam::vec2(10., 0.),
+                glam::vec2(10., 10.),
+                glam::vec2(0., 10.),
+            ],
+            glam::Vec4::ONE,
+        );
+        assert_eq!(lines.to_prep.len(), 6);
+
+        // Each segment ends where the next one starts
+        assert_eq!(lines.to_prep[1].pos, lines.to_prep[2].pos);
+        assert_eq!(lines.to_prep[3].pos, lines.to_prep[4].pos);
+    }
+
+    #[test]
+    fn world_per_pixel_matches_camera_extents() {
+        // A 1600x1200 window at a scale factor of 2 shows 800x600 world units
+        let window_size = Size {
+            width: 1600,
+            height: 1200,
+        };
+        let view_size = logical_view_size(window_size, 2.);
+        assert_eq!(view_size, glam::vec2(800., 600.));
+
+        let world_per_pixel = world_per_pixel(view_size, window_size.width);
+        assert_eq!(world_per_pixel, 0.5);
+
+        // The window's corners land on the edges of what the camera shows
+        let controller = CameraController {
+            zoom: 2.,
+            pan: glam::vec2(30., 10.),
+            ..Default::default()
+        };
+        let half = controller.half_extents(view_size);
+        let corner = glam::vec2(window_size.width as f32, window_size.height as f32);
+
+        assert_eq!(
+            controller.screen_to_world(glam::Vec2::ZERO, window_size, world_per_pixel),
+            controller.pan + glam::vec2(-half.x, half.y)
+        );
+        assert_eq!(
+            controller.screen_to_world(corner, window_size, world_per_pixel),
+            controller.pan + glam::vec2(half.x, -half.y)
+        );
+    }
+
+    #[test]
+    fn frame_stats_count_prepped_geometry() {
+        let mut circles = CircleQueue::new(CirclePipeline::MIN_INSTANCE_CAPACITY);
+        (0..5).for_each(|index| circles.prep_circle(CircleInstance::new([index as f32, 0.], 5.)));
+
+        // Circles only count once they've been uploaded
+        let stats = FrameStats::collect(&circles, &PolygonInstances::default());
+        assert_eq!(stats.circle_instances, 0);
+        assert_eq!(stats.circle_capacity, CirclePipeline::MIN_INSTANCE_CAPACITY);
+
+        // What `finish_prep` records after uploading
+        circles.finish();
+
+        let mut polygons = PolygonInstances::default();
+        let vertex = PolygonVertex {
+            pos: glam::Vec2::ZERO,
+            depth: 0.,
+            t: 0.,
+            color: glam::Vec4::ONE,
+        };
+        polygons.push(PolygonInstance::headless(&[vertex; 3], &[0, 1, 2]));
+
+        let stats = FrameStats::collect(&circles, &polygons);
+        assert_eq!(stats.circle_instances, 5);
+        assert_eq!(stats.polygon_vertices, 3);
+        assert_eq!(stats.polygon_indices, 3);
+        assert_eq!(stats.visible_polygons, 1);
+    }
+
+    #[test]
+    fn color_fade_reaches_target_within_duration() {
+        let (from, to) = (glam::vec4(0., 0., 0., 1.), glam::vec4(1., 0.5, 0.25, 1.));
+        let mut fade = ColorFade::new(from, to, 0.5);
+        assert_eq!(fade.current(), from);
+
+        // Halfway through is halfway between the colors
+        assert!(fade.step(0.25).distance(from.lerp(to, 0.5)) < 1e-6);
+        assert!(!fade.finished());
+
+        // Finishes at the duration, then stays on the target color
+        (0..15).for_each(|_| {
+            fade.step(1. / 60.);
+        });
+        assert!(fade.finished());
+        assert_eq!(fade.current(), to);
+        assert_eq!(fade.step(1.), to);
+
+        // A zero length fade jumps straight to the target
+        assert_eq!(ColorFade::new(from, to, 0.).current(), to);
+    }
+
+    #[test]
+    fn circles_default_to_full_arc() {
+        let circle = CircleInstance::new([0., 0.], 10.);
+        assert_eq!(circle.arc(), (0., TAU));
+        assert!(circle.is_full_circle());
+
+        let arc = circle.with_arc(0.5, 2.);
+        assert_eq!(arc.arc(), (0.5, 2.));
+        assert!(!arc.is_full_circle());
+    }
+
+    /// Triangle fan vertices and indices for a polygon with the given number of sides
+    fn test_polygon(sides: u16) -> (Vec<PolygonVertex>, Vec<u16>) {
+        let vertices = (0..sides)
+            .map(|side| PolygonVertex {
+                pos: glam::Vec2::from_angle(side as f32 / sides as f32 * TAU) * 10.,
+                depth: 0.,
+                t: 0.,
+                color: glam::Vec4::ONE,
+            })
+            .collect();
+        let indices = (1..sides - 1)
+            .flat_map(|side| [0, side, side + 1])
+            .collect();
+
+        (vertices, indices)
+    }
+
+    /// Create a headless polygon for each side count, added to `polygons` in order
+    fn push_polygons<const N: usize>(
+        polygons: &mut PolygonInstances,
+        sides: [u16; N],
+    ) -> [PolygonInstance; N] {
+        sides.map(|sides| {
+            let (vertices, indices) = test_polygon(sides);
+            let instance = PolygonInstance::headless(&vertices, &indices);
+            polygons.push(instance.clone());
+            instance
+        })
+    }
+
+    #[test]
+    fn batch_holds_every_instances_indices() {
+        let mut polygons = PolygonInstances::default();
+        let _instances = push_polygons(&mut polygons, [3, 5, 8]);
+
+        let (_, index_count) = polygons.geometry_count();
+        assert_eq!(index_count, (1 + 3 + 6) * 3);
+
+        let (vertices, indices) = polygons.combine().unwrap();
+        assert_eq!(vertices.len(), 3 + 5 + 8);
+        assert_eq!(indices.len() as u32, index_count);
+
+        // Each instance's indices are offset past the vertices of the ones before it
+        assert_eq!(indices[3], 3);
+        assert_eq!(indices.iter().max(), Some(&(3 + 5 + 8 - 1)));
+    }
+
+    #[test]
+    fn zoomed_screen_corner_lands_on_visible_edge() {
+        let window_size = Size {
+            width: 800,
+            height: 600,
+        };
+        let view_size = logical_view_size(window_size, 1.);
+        let world_per_pixel = world_per_pixel(view_size, window_size.width);
+
+        let controller = CameraController {
+            zoom: 0.5,
+            ..Default::default()
+        };
+
+        // Zoomed in, the bottom right corner is the edge of a smaller visible area
+        let extents = controller.half_extents(view_size);
+        assert_eq!(extents, glam::vec2(200., 150.));
+        assert_eq!(
+            controller.screen_to_world(glam::vec2(800., 600.), window_size, world_per_pixel),
+            glam::vec2(extents.x, -extents.y)
+        );
+        assert_eq!(
+            controller.screen_to_world(glam::vec2(600., 150.), window_size, world_per_pixel),
+            glam::vec2(100., 75.)
+        );
+    }
+
+    #[test]
+    fn circle_layout_matches_vertex_attributes() {
+        let layout = CircleInstance::desc();
+        assert_eq!(
+            layout.array_stride as usize,
+            std::mem::size_of::<CircleInstance>()
+        );
+
+        let offsets = layout
+            .attributes
+            .iter()
+            .map(|attribute| attribute.offset as usize)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            offsets,
+            [
+                std::mem::offset_of!(CircleInstance, pos),
+                std::mem::offset_of!(CircleInstance, radius),
+                std::mem::offset_of!(CircleInstance, border_radius),
+                std::mem::offset_of!(CircleInstance, color),
+                std::mem::offset_of!(CircleInstance, border_color),
+                std::mem::offset_of!(CircleInstance, arc_start),
+            ]
+        );
+
+        // The border radius should land where the shader reads it
+        let offset = offsets[2];
+        [0., 1., 2.5, 10.].into_iter().for_each(|border| {
+            let circle = CircleInstance::new([0., 0.], 20.).with_border(border, glam::Vec4::ONE);
+            let bytes = bytemuck::bytes_of(&circle);
+            assert_eq!(
+                bytemuck::pod_read_unaligned::<f32>(&bytes[offset..offset + 4]),
+                border
+            );
         });
     }
+
+    #[test]
+    fn hidden_polygons_are_not_drawn() {
+        let mut polygons = PolygonInstances::default();
+        let [mut arm, _body] = push_polygons(&mut polygons, [4, 4]);
+        assert_eq!(polygons.visible_instance_count(), 2);
+
+        arm.set_visible(false);
+        assert_eq!(polygons.visible_instance_count(), 1);
+        assert_eq!(polygons.combine().unwrap().0.len(), 4);
+
+        arm.set_visible(true);
+        assert_eq!(polygons.visible_instance_count(), 2);
+    }
+
+    #[test]
+    fn polygons_draw_in_layer_order() {
+        let mut polygons = PolygonInstances::default();
+        let mut instances = push_polygons(&mut polygons, [3, 3, 3]);
+        instances
+            .iter_mut()
+            .zip([2, 0, 1])
+            .for_each(|(instance, layer)| instance.set_layer(layer));
+
+        assert!(polygons.sort_by_layer());
+        let layers = polygons
+            .0
+            .iter()
+            .map(|instance| instance.layer())
+            .collect::<Vec<_>>();
+        assert_eq!(layers, [0, 1, 2]);
+        assert!(Rc::ptr_eq(&polygons.0[0].0, &instances[1].0));
+        assert!(Rc::ptr_eq(&polygons.0[2].0, &instances[0].0));
+
+        // Already in order, so nothing needs rebuilding
+        assert!(!polygons.sort_by_layer());
+    }
+
+    /// A headless device for tests that need the gpu. Returns `None` when no adapter is available.
+    fn test_device() -> Option<(Device, Queue)> {
+        let instance = wgpu::Instance::default();
+        let adapter =
+            pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))?;
+
+        pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).ok()
+    }
+
+    /// Surface settings for building pipelines without a window
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    fn test_config() -> SurfaceConfig {
+        wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            width: 800,
+            height: 600,
+            present_mode: wgpu::PresentMode::AutoVsync,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: Vec::new(),
+        }
+    }
+
+    /// Draw a white quad covering the view, with its gradient running from 0 on the left to 1 on
+    /// the right, and read it back. The camera is left as the identity so the quad fills the view.
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    fn render_gradient_quad(device: &Device, queue: &Queue, strength: f32) -> image::RgbaImage {
+        use wgpu::util::DeviceExt;
+
+        let config = test_config();
+        let shared = SharedRenderResources::new(device);
+        let mut polygons = PolygonPipeline::new(device, &config, &shared);
+        polygons.set_gradient_strength(queue, strength);
+
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[glam::Mat4::IDENTITY.to_cols_array(), [0.; 16]]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: shared.camera_bind_group_layout(),
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let vertices =
+            [(-1., -1., 0.), (1., -1., 1.), (1., 1., 1.), (-1., 1., 0.)].map(|(x, y, t)| {
+                PolygonVertex {
+                    pos: glam::vec2(x, y),
+                    depth: 0.,
+                    t,
+                    color: glam::Vec4::ONE,
+                }
+            });
+        let _quad = polygons.new_polygon(device, &vertices, &[0, 1, 2, 0, 2, 3]);
+        polygons.finish_prep(device, queue);
+
+        let texture = create_capture_texture(device, config.format, 64, 8);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let depth_view = create_depth_texture(device, 64, 8);
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations::default(),
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            polygons.render(&mut pass, &camera_bind_group);
+        }
+        queue.submit(Some(encoder.finish()));
+
+        read_texture(device, queue, &texture, true).unwrap()
+    }
+
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    #[test]
+    #[ignore = "needs a gpu"]
+    fn gradient_darkens_towards_the_tail() {
+        let (device, queue) = test_device().expect("No gpu adapter available");
+        let brightness = |image: &image::RgbaImage, x: u32| image.get_pixel(x, 4).0[0];
+
+        // Off by default, so the whole quad keeps its vertex color
+        let flat = render_gradient_quad(&device, &queue, 0.);
+        assert_eq!(brightness(&flat, 0), 255);
+        assert_eq!(brightness(&flat, 63), 255);
+
+        let shaded = render_gradient_quad(&device, &queue, 0.5);
+        assert!(brightness(&shaded, 0) > 250);
+        assert!(brightness(&shaded, 32) < brightness(&shaded, 0));
+        assert!(brightness(&shaded, 63) < brightness(&shaded, 32));
+    }
+
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    #[test]
+    fn capture_rows_are_unpadded() {
+        // Width isn't a multiple of the row alignment, so every row has padding to strip
+        let (width, height) = (333, 177);
+        let padded = padded_row_bytes(width) as usize;
+        assert_eq!(padded % wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize, 0);
+        assert!(padded > width as usize * 4);
+
+        let data = (0..height)
+            .flat_map(|row| {
+                let mut bytes = vec![row as u8; padded];
+                bytes[width as usize * 4..].fill(u8::MAX);
+                bytes
+            })
+            .collect::<Vec<_>>();
+
+        let pixels = unpad_rows(&data, width);
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+        pixels
+            .chunks(width as usize * 4)
+            .enumerate()
+            .for_each(|(row, bytes)| assert!(bytes.iter().all(|byte| *byte == row as u8)));
+    }
+
+    #[cfg(all(feature = "recording", not(target_arch = "wasm32")))]
+    #[test]
+    #[ignore = "needs a gpu"]
+    fn capture_matches_window_size() {
+        let (device, queue) = test_device().expect("No gpu adapter available");
+
+        // Width isn't a multiple of the row alignment, so the readback has to strip padding
+        let window_size = Size {
+            width: 333,
+            height: 177,
+        };
+        let texture = create_capture_texture(
+            &device,
+            wgpu::TextureFormat::Bgra8UnormSrgb,
+            window_size.width,
+            window_size.height,
+        );
+
+        let image = read_texture(&device, &queue, &texture, true).unwrap();
+        assert_eq!(image.dimensions(), (window_size.width, window_size.height));
+    }
+
+    #[test]
+    fn textures_are_at_least_one_pixel() {
+        // A minimised window has no size, but still needs a valid depth texture
+        let size = texture_size(0, 0);
+        assert_eq!((size.width, size.height), (1, 1));
+
+        let size = texture_size(800, 600);
+        assert_eq!((size.width, size.height), (800, 600));
+    }
+
+    #[test]
+    #[ignore = "needs a gpu"]
+    fn depth_texture_is_created_for_any_size() {
+        let (device, _queue) = test_device().expect("No gpu adapter available");
+
+        [(800, 600), (0, 0)]
+            .into_iter()
+            .for_each(|(width, height)| {
+                device.push_error_scope(wgpu::ErrorFilter::Validation);
+                let _view = create_depth_texture(&device, width, height);
+                let error = pollster::block_on(device.pop_error_scope());
+                assert!(error.is_none(), "{}x{}: {:?}", width, height, error);
+            });
+    }
+
+    #[test]
+    fn polygon_vertices_carry_depth() {
+        let vertex = PolygonVertex {
+            pos: glam::vec2(1., 2.),
+            depth: 0.75,
+            t: 0.5,
+            color: glam::Vec4::ONE,
+        };
+
+        // Depth shares the first attribute with the position, read by the shader as z
+        let offset = std::mem::offset_of!(PolygonVertex, depth);
+        assert_eq!(offset, std::mem::size_of::<glam::Vec2>());
+        assert_eq!(PolygonVertex::desc().attributes[0].offset, 0);
+        assert_eq!(
+            PolygonVertex::desc().attributes[0].format,
+            wgpu::VertexFormat::Float32x4
+        );
+
+        let bytes = bytemuck::bytes_of(&vertex);
+        assert_eq!(
+            bytemuck::pod_read_unaligned::<f32>(&bytes[offset..offset + 4]),
+            0.75
+        );
+    }
+
+    #[test]
+    fn labels_are_flushed_each_frame() {
+        let mut lines = LineQueue::default();
+        let mut labels = vec![
+            (glam::Vec2::ZERO, "1".to_string()),
+            (glam::vec2(0., 50.), "7".to_string()),
+        ];
+
+        // A 1 lights two segments and a 7 lights three
+        flush_labels(&mut labels, &mut lines, 10.);
+        assert!(labels.is_empty());
+        assert_eq!(lines.to_prep.len(), (2 + 3) * 2);
+
+        // The labels were drained, so the next frame adds nothing
+        flush_labels(&mut labels, &mut lines, 10.);
+        assert_eq!(lines.to_prep.len(), (2 + 3) * 2);
+    }
+
+    #[test]
+    fn screen_centre_and_corner_to_world() {
+        let window_size = Size {
+            width: 800,
+            height: 600,
+        };
+        let mut controller = CameraController::default();
+
+        let centre = glam::vec2(400., 300.);
+        let top_left = glam::Vec2::ZERO;
+        let bottom_right = glam::vec2(800., 600.);
+
+        assert_eq!(
+            controller.screen_to_world(centre, window_size, 1.),
+            glam::Vec2::ZERO
+        );
+        assert_eq!(
+            controller.screen_to_world(top_left, window_size, 1.),
+            glam::vec2(-400., 300.)
+        );
+        assert_eq!(
+            controller.screen_to_world(bottom_right, window_size, 2.),
+            glam::vec2(800., -600.)
+        );
+
+        controller.zoom = 2.;
+        controller.pan = glam::vec2(50., -25.);
+        assert_eq!(
+            controller.screen_to_world(centre, window_size, 1.),
+            controller.pan
+        );
+        assert_eq!(
+            controller.screen_to_world(top_left, window_size, 1.),
+            glam::vec2(-750., 575.)
+        );
+    }
 }
@@ -1,73 +1,206 @@
 use core::f32;
 use std::collections::HashMap;
 
-use roots_core::common::Time;
+use roots_core::{
+    common::{input::Input, Time},
+    runner::prelude::{KeyCode, MouseButton},
+};
 
 use crate::{
-    ik::{self, ForwardKinematic, InverseKinematic, Node, NodeID, NodeManager},
-    polygon_manager::{PolygonManager, PolygonNode},
-    renderer::{CircleInstance, PolygonInstance, Renderer},
+    creature_spec::CreatureSpec,
+    ik::{
+        self, Axis, ChainPhysics, ChainRegistry, ChainSpring, ForwardKinematic, InverseKinematic,
+        Node, NodeID, NodeManager, SolveResult, Wind,
+    },
+    polygon_manager::{self, PolygonManager, PolygonNode},
+    renderer::{CircleInstance, PolygonInstance, PolygonVertex, RenderContext},
 };
 
-pub enum SubState {
-    IK(IKSubstate),
-    FK(FKSubstate),
-    Creature(CreatureSubstate),
-    Bridge(BridgeSubstate),
-}
+/// A scene of chains that is updated and drawn each frame.
+/// Implement this and register a constructor with the state to add new substates.
+pub trait Substate {
+    /// Handle edge triggered input (just pressed or released) once per frame, before any updates.
+    /// Updates can run any number of times a frame, so they should only check held input.
+    fn handle_input(
+        &mut self,
+        _node_manager: &mut NodeManager,
+        _mouse_pos: glam::Vec2,
+        _mouse_buttons: &Input<MouseButton>,
+        _keys: &Input<KeyCode>,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        time: SimTime,
+        node_manager: &mut NodeManager,
+        mouse_pos: glam::Vec2,
+        mouse_buttons: &Input<MouseButton>,
+        keys: &Input<KeyCode>,
+    );
+
+    fn render(
+        &mut self,
+        node_manager: &NodeManager,
+        renderer: &mut dyn RenderContext,
+        mouse_pos: glam::Vec2,
+    );
+
+    /// All chains currently used by this substate, in order from root to tip
+    fn chains(&self) -> Vec<&[NodeID]>;
+
+    /// Register every chain in this substate along with its last solve result
+    fn report_chains(&self, _registry: &mut ChainRegistry) {}
 
-impl SubState {
+    /// Draw the outline of every polygon in this substate as lines
+    fn render_outlines(&self, _node_manager: &NodeManager, _renderer: &mut dyn RenderContext) {}
+
+    /// Background color used while this substate is the newest
     #[inline]
-    pub fn new_ik(node_manager: &mut NodeManager) -> Self {
-        Self::IK(IKSubstate::new(node_manager))
+    fn clear_color(&self) -> glam::Vec4 {
+        glam::vec4(0.1, 0.1, 0.1, 1.)
     }
 
+    /// Current size relative to when the substate was created, if it can be resized
     #[inline]
-    pub fn new_fk(node_manager: &mut NodeManager, renderer: &mut Renderer) -> Self {
-        Self::FK(FKSubstate::new(node_manager, renderer))
+    fn scale(&self) -> Option<f32> {
+        None
     }
 
+    fn set_scale(&mut self, _node_manager: &mut NodeManager, _scale: f32) {}
+
+    /// Remove every node used by this substate, leaving any other substates untouched
+    fn remove_nodes(&self, node_manager: &mut NodeManager) {
+        self.chains().into_iter().for_each(|chain| {
+            node_manager.remove_nodes(chain);
+        });
+    }
+}
+
+/// Creates a substate, inserting its nodes and polygons
+pub type SubstateConstructor = fn(&mut NodeManager, &mut dyn RenderContext) -> Box<dyn Substate>;
+
+/// The built in substates, in the order they are cycled through
+pub fn default_substates() -> Vec<SubstateConstructor> {
+    vec![
+        |node_manager, _| Box::new(IKSubstate::new(node_manager)),
+        |node_manager, renderer| Box::new(FKSubstate::new(node_manager, renderer)),
+        |node_manager, renderer| Box::new(CreatureSubstate::new(node_manager, renderer)),
+        |node_manager, renderer| Box::new(BridgeSubstate::new(node_manager, renderer)),
+        |node_manager, renderer| Box::new(MirrorSubstate::new(node_manager, renderer)),
+    ]
+}
+
+const OUTLINE_COLOR: glam::Vec4 = glam::vec4(0.9, 0.9, 0.9, 1.);
+
+/// Time step used by the simulation, decoupled from the window's clock
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimTime {
+    pub dt: f32,
+}
+
+impl From<&Time> for SimTime {
     #[inline]
-    pub fn new_creature(node_manager: &mut NodeManager, renderer: &mut Renderer) -> Self {
-        Self::Creature(CreatureSubstate::new(node_manager, renderer))
+    fn from(value: &Time) -> Self {
+        Self {
+            dt: value.delta_seconds(),
+        }
     }
+}
 
+impl SimTime {
     #[inline]
-    pub fn new_bridge(node_manager: &mut NodeManager, renderer: &mut Renderer) -> Self {
-        Self::Bridge(BridgeSubstate::new(node_manager, renderer))
+    pub fn new(dt: f32) -> Self {
+        Self { dt }
     }
 
     #[inline]
-    pub fn update(&mut self, time: &Time, node_manager: &mut NodeManager, mouse_pos: glam::Vec2) {
-        match self {
-            SubState::IK(ik) => ik.update(node_manager, mouse_pos),
-            SubState::FK(fk) => fk.update(node_manager, mouse_pos),
-            SubState::Creature(creature) => creature.update(node_manager, mouse_pos),
-            SubState::Bridge(bridge) => bridge.update(time, node_manager, mouse_pos),
-        }
+    pub fn delta_seconds(&self) -> f32 {
+        self.dt
     }
+}
+
+/// Splits variable frame times into a whole number of fixed size steps,
+/// so the simulation behaves the same at any frame rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FixedTimestep {
+    pub step: f32,
+    /// Most steps to run in a single frame, to stop a long frame causing a spiral of slower frames
+    pub max_steps: usize,
+    accumulator: f32,
+}
 
+impl FixedTimestep {
     #[inline]
-    pub fn render(
-        &mut self,
-        node_manager: &mut NodeManager,
-        renderer: &mut Renderer,
-        mouse_pos: glam::Vec2,
-    ) {
-        match self {
-            SubState::IK(ik) => ik.render(renderer, mouse_pos),
-            SubState::FK(fk) => fk.render(node_manager, renderer),
-            SubState::Creature(creature) => creature.render(node_manager, renderer),
-            SubState::Bridge(bridge) => bridge.render(&node_manager, renderer, mouse_pos),
+    pub fn new(step: f32, max_steps: usize) -> Self {
+        Self {
+            step,
+            max_steps,
+            accumulator: 0.,
+        }
+    }
+
+    /// Add the elapsed time and return how many fixed steps should be run.
+    /// Any time past `max_steps` is dropped.
+    pub fn advance(&mut self, delta_seconds: f32) -> usize {
+        if self.step <= 0. {
+            return 0;
+        }
+
+        self.accumulator += delta_seconds.max(0.);
+
+        let steps = (self.accumulator / self.step) as usize;
+        self.accumulator -= steps as f32 * self.step;
+
+        if steps > self.max_steps {
+            self.accumulator = 0.;
+            return self.max_steps;
         }
+
+        steps
+    }
+
+    #[inline]
+    pub fn sim_time(&self) -> SimTime {
+        SimTime::new(self.step)
+    }
+}
+
+/// Outline color for a node at the given index of a chain, alternating between even and odd nodes
+#[inline]
+pub fn outline_color(index: usize) -> glam::Vec4 {
+    match index % 2 {
+        0 => glam::vec4(0.9, 0.9, 0.9, 1.),
+        _ => glam::vec4(0.9, 0.5, 0.2, 1.),
     }
 }
 
+/// Minimum speed (in units per second) the mouse must move for a head to turn towards it
+const HEAD_TURN_SPEED: f32 = 60.;
+
+/// Check if the mouse moved fast enough over the frame for the head to turn to face it
+#[inline]
+fn head_should_turn(mouse_delta: glam::Vec2, delta_seconds: f32) -> bool {
+    if delta_seconds <= 0. {
+        return false;
+    }
+
+    mouse_delta.length() / delta_seconds > HEAD_TURN_SPEED
+}
+
 pub struct IKSubstate {
     ik: InverseKinematic,
+    last_result: SolveResult,
+    /// Anchor follows the mouse instead of the target while set
+    dragging_anchor: bool,
+    /// Target held in place while not following the mouse
+    pinned_target: Option<glam::Vec2>,
 }
 
 impl IKSubstate {
+    /// How close (in world units) a shift click has to be to the anchor to start dragging it
+    const ANCHOR_GRAB_DISTANCE: f32 = 40.;
+
     pub fn new(node_manager: &mut NodeManager) -> Self {
         let nodes = node_manager.insert_nodes(&[
             Node {
@@ -88,39 +221,137 @@ impl IKSubstate {
             Node::new(40.),
         ]);
 
-        let ik = InverseKinematic {
-            nodes: nodes.clone(),
-            anchor: Some(glam::vec2(0., -100.)),
-            target: glam::vec2(0., 0.),
-            cycles: 10,
+        let ik = InverseKinematic::builder(nodes)
+            .anchor(glam::vec2(0., -100.))
+            .build();
+
+        Self {
+            ik,
+            last_result: SolveResult::default(),
+            dragging_anchor: false,
+            pinned_target: None,
+        }
+    }
+
+    /// Start dragging when grabbed and stop once the button is no longer held
+    #[inline]
+    fn update_drag(&mut self, grabbed: bool, held: bool) {
+        self.dragging_anchor = (self.dragging_anchor || grabbed) && held;
+    }
+
+    /// Toggling switches between following the mouse and pinning the target where the mouse is.
+    /// Clicking while pinned moves the pin to the mouse.
+    fn update_pin(&mut self, mouse_pos: glam::Vec2, clicked: bool, toggled: bool) {
+        self.pinned_target = match (self.pinned_target, toggled) {
+            (Some(_), true) => None,
+            (None, true) => Some(mouse_pos),
+            (Some(_), false) if clicked => Some(mouse_pos),
+            (pinned, false) => pinned,
         };
+    }
+}
+
+impl Substate for IKSubstate {
+    /// Holding shift and clicking near the anchor drags it around until the button is released.
+    /// Right click toggles between the target following the mouse and being pinned,
+    /// with left click moving the pin.
+    fn handle_input(
+        &mut self,
+        _node_manager: &mut NodeManager,
+        mouse_pos: glam::Vec2,
+        mouse_buttons: &Input<MouseButton>,
+        keys: &Input<KeyCode>,
+    ) {
+        let modifier = keys.pressed(KeyCode::ShiftLeft) || keys.pressed(KeyCode::ShiftRight);
+        let near_anchor = self
+            .ik
+            .anchor
+            .is_some_and(|anchor| anchor.distance(mouse_pos) <= Self::ANCHOR_GRAB_DISTANCE);
+
+        self.update_drag(
+            modifier && near_anchor && mouse_buttons.just_pressed(MouseButton::Left),
+            mouse_buttons.pressed(MouseButton::Left),
+        );
 
-        Self { ik }
+        self.update_pin(
+            mouse_pos,
+            !self.dragging_anchor && mouse_buttons.just_pressed(MouseButton::Left),
+            mouse_buttons.just_pressed(MouseButton::Right),
+        );
     }
 
-    pub fn update(&mut self, node_manager: &mut NodeManager, mouse_pos: glam::Vec2) {
-        self.ik.target = mouse_pos;
-        ik::fabrik(node_manager, &self.ik);
+    /// Move the anchor while dragging, otherwise the target follows the mouse or its pin
+    fn update(
+        &mut self,
+        _time: SimTime,
+        node_manager: &mut NodeManager,
+        mouse_pos: glam::Vec2,
+        _mouse_buttons: &Input<MouseButton>,
+        _keys: &Input<KeyCode>,
+    ) {
+        match self.dragging_anchor {
+            true => self.ik.anchor = Some(mouse_pos),
+            false => self.ik.target = self.pinned_target.unwrap_or(mouse_pos).into(),
+        }
+
+        self.last_result = self.ik.solve(node_manager);
     }
 
-    pub fn render(&mut self, renderer: &mut Renderer, mouse_pos: glam::Vec2) {
+    fn render(
+        &mut self,
+        _node_manager: &NodeManager,
+        renderer: &mut dyn RenderContext,
+        mouse_pos: glam::Vec2,
+    ) {
+        if let Some(anchor) = self.ik.anchor {
+            let color = match self.dragging_anchor {
+                true => glam::vec4(1., 1., 0., 1.),
+                false => glam::vec4(0.5, 0.5, 0.5, 1.),
+            };
+            renderer.prep_circle(
+                CircleInstance::new(anchor, Self::ANCHOR_GRAB_DISTANCE)
+                    .hollow()
+                    .with_border(2., color),
+            );
+        }
+
+        if let Some(pinned) = self.pinned_target {
+            renderer.prep_circle(
+                CircleInstance::new(pinned, 10.)
+                    .hollow()
+                    .with_border(3., glam::vec4(1., 0., 0., 1.)),
+            );
+        }
+
         renderer
-            .circle_pipeline
             .prep_circle(CircleInstance::new(mouse_pos, 5.).with_color(glam::vec4(1., 0., 0., 1.)));
     }
+
+    #[inline]
+    fn chains(&self) -> Vec<&[NodeID]> {
+        vec![&self.ik.nodes]
+    }
+
+    #[inline]
+    fn report_chains(&self, registry: &mut ChainRegistry) {
+        registry.register_ik("ik", &self.ik, self.last_result);
+    }
 }
 
 pub struct FKSubstate {
     fk: ForwardKinematic,
+    pub spring: ChainSpring,
     prev_mouse_pos: glam::Vec2,
     prev_mouse_delta: glam::Vec2,
 
     polygons: PolygonManager,
     instance: PolygonInstance,
+    /// Whether the tail is joined back to the head to draw the chain as a ring
+    closed: bool,
 }
 
 impl FKSubstate {
-    pub fn new(node_manager: &mut NodeManager, renderer: &mut Renderer) -> Self {
+    pub fn new(node_manager: &mut NodeManager, renderer: &mut dyn RenderContext) -> Self {
         let data = &[
             [Node::new(50.); 4].as_slice(),
             [Node::new(40.); 5].as_slice(),
@@ -134,41 +365,72 @@ impl FKSubstate {
 
         let polygons = PolygonManager::default();
         let (vertices, indices) =
-            polygons.calculate_vertices(&node_manager, &fk.nodes, glam::Vec4::ONE, None, None);
+            polygons.calculate_vertices(&node_manager, &fk.nodes, None, None, None);
 
-        let instance = renderer
-            .polygon_pipeline
-            .new_polygon(&renderer.device, &vertices, &indices);
+        let instance = renderer.new_polygon(&vertices, &indices);
 
         Self {
             fk,
+            spring: ChainSpring::new(30.),
             prev_mouse_pos: glam::Vec2::ZERO,
             prev_mouse_delta: glam::Vec2::ZERO,
             polygons,
             instance,
+            closed: false,
         }
     }
+}
 
-    pub fn update(&mut self, node_manager: &mut NodeManager, mouse_pos: glam::Vec2) {
-        let node = node_manager.get_node_mut(&self.fk.nodes[0]).unwrap();
-        node.pos = mouse_pos;
+impl Substate for FKSubstate {
+    /// O switches between drawing the chain as a ring and as an open strip
+    fn handle_input(
+        &mut self,
+        _node_manager: &mut NodeManager,
+        _mouse_pos: glam::Vec2,
+        _mouse_buttons: &Input<MouseButton>,
+        keys: &Input<KeyCode>,
+    ) {
+        if keys.just_pressed(KeyCode::KeyO) {
+            self.closed = !self.closed;
+            self.polygons.with_closed(self.closed);
+        }
+    }
 
+    fn update(
+        &mut self,
+        time: SimTime,
+        node_manager: &mut NodeManager,
+        mouse_pos: glam::Vec2,
+        _mouse_buttons: &Input<MouseButton>,
+        _keys: &Input<KeyCode>,
+    ) {
+        // Measure the speed over this step only, keeping the last turning direction otherwise
         let mouse_delta = mouse_pos - self.prev_mouse_pos;
-        let delta_len = mouse_delta.length();
+        self.prev_mouse_pos = mouse_pos;
 
-        if delta_len > 1. {
-            node.rotation = mouse_delta.to_angle();
-            self.prev_mouse_pos = mouse_pos;
+        if head_should_turn(mouse_delta, time.delta_seconds()) {
             self.prev_mouse_delta = mouse_delta;
         }
 
-        ik::process_fk(node_manager, &self.fk);
+        self.spring.process(
+            node_manager,
+            &self.fk,
+            Some((mouse_pos, self.prev_mouse_delta.to_angle())),
+            time.delta_seconds(),
+        );
     }
 
-    pub fn render(&mut self, node_manager: &NodeManager, renderer: &mut Renderer) {
-        let head = node_manager.get_node(&self.fk.nodes[0]).unwrap();
+    fn render(
+        &mut self,
+        node_manager: &NodeManager,
+        renderer: &mut dyn RenderContext,
+        _mouse_pos: glam::Vec2,
+    ) {
+        let Some(head) = self.fk.root().and_then(|id| node_manager.get_node(id)) else {
+            return;
+        };
 
-        renderer.circle_pipeline.prep_circle(
+        renderer.prep_circle(
             CircleInstance::new(
                 head.pos + (self.prev_mouse_delta.normalize_or_zero() * 20.),
                 5.,
@@ -176,46 +438,104 @@ impl FKSubstate {
             .with_color(glam::vec4(1., 0., 0., 1.)),
         );
 
-        let (vertices, indices) = self.polygons.calculate_vertices(
-            &node_manager,
-            &self.fk.nodes,
-            glam::Vec4::ONE,
-            None,
-            None,
+        let (vertices, indices) =
+            self.polygons
+                .calculate_vertices(&node_manager, &self.fk.nodes, None, None, None);
+
+        renderer.update_polygon(&mut self.instance, &vertices, &indices);
+    }
+
+    #[inline]
+    fn chains(&self) -> Vec<&[NodeID]> {
+        vec![&self.fk.nodes]
+    }
+
+    #[inline]
+    fn report_chains(&self, registry: &mut ChainRegistry) {
+        registry.register_fk("fk", &self.fk);
+    }
+
+    fn render_outlines(&self, node_manager: &NodeManager, renderer: &mut dyn RenderContext) {
+        renderer.prep_strip(
+            &self
+                .polygons
+                .outline_path(node_manager, &self.fk.nodes, &[]),
+            OUTLINE_COLOR,
         );
+    }
 
-        self.instance
-            .update(&renderer.device, &renderer.queue, &vertices, &indices);
+    #[inline]
+    fn clear_color(&self) -> glam::Vec4 {
+        glam::vec4(0.08, 0.1, 0.14, 1.)
     }
 }
 
 pub struct CreatureSubstate {
     body: ForwardKinematic,
+    /// Gives the body some lag and overshoot as it follows the head
+    pub spring: ChainSpring,
+    /// Index of the first body node after the legs
+    tail_start: usize,
+    scale: f32,
+    /// Height of the ground line limbs are kept above
+    pub ground_y: Option<f32>,
+    /// Keep limbs from passing through the body
+    pub limb_collision: bool,
+    _ground: Option<PolygonInstance>,
     prev_mouse_pos: glam::Vec2,
     prev_mouse_delta: glam::Vec2,
+    pub wind: Wind,
+    elapsed: f32,
 
     polygons: PolygonManager,
     polygon_body: PolygonInstance,
 
-    arm_right: CreatureLimb,
-    arm_left: CreatureLimb,
-    leg_right: CreatureLimb,
-    leg_left: CreatureLimb,
+    limbs: Vec<CreatureLimb>,
 }
 
 pub struct CreatureLimb {
     ik: InverseKinematic,
+    last_result: SolveResult,
+    node_data: Vec<Node>,
+    custom: HashMap<usize, PolygonNode>,
     polygons: PolygonManager,
     instance: PolygonInstance,
     limb_reach_range: f32,
     limb_reach_angle: f32,
     color: glam::Vec4,
+    /// Direction relative to the limb root that the joints bend towards
+    pub pole_angle: Option<f32>,
+
+    /// Whether the limb reaches towards the mouse while it is held
+    pub grabs: bool,
+    /// Position the limb should reach towards instead of resting
+    pub reach_target: Option<glam::Vec2>,
+    reach_pos: glam::Vec2,
+    reach_blend: f32,
+    /// Position the foot rests at or is stepping towards
+    rest_target: glam::Vec2,
+    /// Position the foot is pinned to while the body moves, until it needs to step
+    planted_pos: Option<glam::Vec2>,
+    /// Number of consecutive updates the limb has failed to reach its target
+    frames_unreached: u32,
 }
 
 impl CreatureLimb {
+    /// How quickly (per second) a limb blends between resting and reaching
+    const REACH_BLEND_SPEED: f32 = 4.;
+    /// How many updates in a row a limb can miss its target before it resets to resting
+    const MAX_UNREACHED_FRAMES: u32 = 3;
+    /// How quickly (per second) an unreached limb eases towards its rest pose
+    const RELAX_SPEED: f32 = 2.;
+    /// How far the resting position can move from a planted foot before it steps,
+    /// as a fraction of the reach range
+    const STEP_THRESHOLD: f32 = 0.5;
+    /// How far past the resting position a step lands, as a fraction of the step threshold
+    const STEP_OVERSHOOT: f32 = 0.5;
+
     pub fn new(
         node_manager: &mut NodeManager,
-        renderer: &mut Renderer,
+        renderer: &mut dyn RenderContext,
         parent: NodeID,
         nodes: &[Node],
         custom: HashMap<usize, PolygonNode>,
@@ -228,255 +548,376 @@ impl CreatureLimb {
         limb_nodes.extend_from_slice(&node_manager.insert_nodes(nodes));
 
         let mut polygons = PolygonManager::default();
-        let custom = custom
-            .into_iter()
+        let custom_nodes = custom
+            .iter()
             .filter_map(|(index, data)| {
-                let node_id = limb_nodes.get(index)?;
-                Some((*node_id, data))
+                let node_id = limb_nodes.get(*index)?;
+                Some((*node_id, *data))
             })
             .collect();
-        polygons.with_custom(custom);
+        polygons.with_custom(custom_nodes);
+        polygons.with_base_color(color);
+        polygons.with_cap_segments(4);
 
-        let ik = InverseKinematic {
-            nodes: limb_nodes,
-            anchor: None,
-            target: glam::Vec2::ZERO,
-            cycles: 10,
-        };
+        let ik = InverseKinematic::new(limb_nodes, None, glam::Vec2::ZERO).with_tolerance(5.);
 
         let (vertices, indices) =
-            polygons.calculate_vertices(&node_manager, &ik.nodes, color, None, None);
-        let instance = renderer
-            .polygon_pipeline
-            .new_polygon(&renderer.device, &vertices, &indices);
+            polygons.calculate_vertices(&node_manager, &ik.nodes, None, None, None);
+        let instance = renderer.new_polygon(&vertices, &indices);
 
         Self {
             ik,
+            last_result: SolveResult::default(),
+            node_data: nodes.to_vec(),
+            custom,
             polygons,
             instance,
             limb_reach_range,
             limb_reach_angle,
             color,
+            pole_angle: None,
+
+            grabs: false,
+            reach_target: None,
+            reach_pos: glam::Vec2::ZERO,
+            reach_blend: 0.,
+            rest_target: glam::Vec2::ZERO,
+            planted_pos: None,
+            frames_unreached: 0,
         }
     }
 
-    pub fn update(&mut self, node_manager: &mut NodeManager) {
-        let limb_root = node_manager.get_node(&self.ik.nodes[0]).unwrap();
+    /// Create a new limb on the same parent that is a mirror image of this one.
+    pub fn mirrored(
+        &self,
+        node_manager: &mut NodeManager,
+        renderer: &mut dyn RenderContext,
+        across: Axis,
+    ) -> Self {
+        let nodes = self
+            .node_data
+            .iter()
+            .map(|node| node.mirror(across))
+            .collect::<Vec<_>>();
+
+        let mut limb = Self::new(
+            node_manager,
+            renderer,
+            self.ik.nodes[0],
+            &nodes,
+            self.custom.clone(),
+            self.limb_reach_range,
+            across.mirror_angle(self.limb_reach_angle),
+            self.color,
+        );
+        limb.grabs = self.grabs;
+        limb.pole_angle = self
+            .pole_angle
+            .map(|pole_angle| across.mirror_angle(pole_angle));
+        limb
+    }
+
+    /// Solve the limb, keeping its target and end node above the ground if given.
+    /// The limb blends between reaching for `reach_target` and stepping between planted rest positions.
+    pub fn update(&mut self, time: SimTime, node_manager: &mut NodeManager, ground_y: Option<f32>) {
+        let Some(limb_root) = self.ik.root().and_then(|id| node_manager.get_node(id)) else {
+            return;
+        };
 
         let limb_root_pos = limb_root.pos;
         let limb_root_rot = limb_root.rotation;
 
-        if !ik::fabrik(node_manager, &self.ik) {
-            let new_target_angle = limb_root_rot + self.limb_reach_angle;
+        let blend_step = Self::REACH_BLEND_SPEED * time.delta_seconds();
+        match self.reach_target {
+            Some(reach_target) => {
+                self.reach_pos = reach_target;
+                self.reach_blend = (self.reach_blend + blend_step).min(1.);
+            }
+            None => self.reach_blend = (self.reach_blend - blend_step).max(0.),
+        }
 
-            let new_target_dir = glam::Vec2::from_angle(new_target_angle);
-            self.ik.target = limb_root_pos + new_target_dir * self.limb_reach_range;
+        let mut target = self.rest_target.lerp(self.reach_pos, self.reach_blend);
+        if let Some(ground_y) = ground_y {
+            target = clamp_to_ground(target, ground_y);
+        }
+        self.ik.target = target.into();
+        self.ik.pole = self.pole_angle.map(|pole_angle| {
+            limb_root_pos
+                + glam::Vec2::from_angle(limb_root_rot + pole_angle) * self.limb_reach_range
+        });
+
+        let result = self.ik.solve(node_manager);
+        self.last_result = result;
+        self.frames_unreached = match result.reached {
+            true => 0,
+            false => self.frames_unreached + 1,
+        };
+
+        let rest_dir = glam::Vec2::from_angle(limb_root_rot + self.limb_reach_angle);
+        let rest_pos = limb_root_pos + rest_dir * self.limb_reach_range;
+
+        // Briefly missing the target keeps the limb stepping as usual
+        if self.frames_unreached() < Self::MAX_UNREACHED_FRAMES {
+            self.update_step(rest_pos);
+        } else {
+            self.planted_pos = None;
+            self.rest_target = rest_pos;
+
+            // Curl up instead of staying stretched towards a target that is out of reach
+            ik::relax(
+                node_manager,
+                &self.ik.nodes,
+                Self::RELAX_SPEED * time.delta_seconds(),
+            );
+        }
+
+        ik::limit_angular_speed(node_manager, &self.ik.nodes, time.delta_seconds());
+
+        let Some(ground_y) = ground_y else {
+            return;
+        };
+
+        if let Some(foot) = self.ik.tip().and_then(|id| node_manager.get_node_mut(id)) {
+            foot.pos = clamp_to_ground(foot.pos, ground_y);
         }
     }
 
-    pub fn render(&mut self, node_manager: &NodeManager, renderer: &mut Renderer) {
-        renderer.circle_pipeline.prep_circle(
-            CircleInstance::new(self.ik.target, 5.).with_color(glam::vec4(0., 1., 0., 1.)),
-        );
+    /// Plant the foot once it reaches where it was stepping to, and keep it there until the
+    /// resting position has moved a step away. Then lift it and step past the resting position.
+    fn update_step(&mut self, rest_pos: glam::Vec2) {
+        let step_threshold = self.limb_reach_range * Self::STEP_THRESHOLD;
+
+        match self.planted_pos {
+            Some(planted) if planted.distance(rest_pos) > step_threshold => {
+                self.planted_pos = None;
+                self.rest_target = rest_pos
+                    + (rest_pos - planted).normalize_or_zero()
+                        * step_threshold
+                        * Self::STEP_OVERSHOOT;
+            }
+            // Only the resting target gets planted, not positions the limb is reaching for
+            None if self.reach_blend <= 0. => self.planted_pos = Some(self.rest_target),
+            _ => {}
+        }
+    }
 
-        let (vertices, indices) = self.polygons.calculate_vertices(
-            node_manager,
-            &self.ik.nodes[1..],
-            self.color,
-            None,
-            None,
-        );
+    /// Scale the limb's rendered widths and reach to match its nodes being scaled by `factor`
+    fn scale(&mut self, factor: f32) {
+        self.node_data.iter_mut().for_each(|node| {
+            node.radius = (node.radius * factor).max(NodeManager::MIN_SCALED_RADIUS)
+        });
+
+        self.custom.values_mut().for_each(|node| {
+            if let Some(radius) = node.radius.as_mut() {
+                *radius = (*radius * factor).max(NodeManager::MIN_SCALED_RADIUS);
+            }
+        });
 
-        self.instance
-            .update(&renderer.device, &renderer.queue, &vertices, &indices);
+        let custom_nodes = self
+            .custom
+            .iter()
+            .filter_map(|(index, data)| Some((*self.ik.nodes.get(*index)?, *data)))
+            .collect();
+        self.polygons.with_custom(custom_nodes);
+
+        self.limb_reach_range *= factor;
     }
-}
 
-impl CreatureSubstate {
-    // const CREATURE_BODY_COLOR: glam::Vec4 = glam::vec4(0.118, 0.29, 0.082, 1.);
-    const CREATURE_BODY_COLOR: glam::Vec4 = glam::vec4(0.2, 0.5, 0., 1.);
-    const CREATURE_LIMB_COLOR: glam::Vec4 = glam::vec4(0.125, 0.412, 0.067, 1.);
+    /// Number of consecutive updates the limb has failed to reach its target.
+    /// Resets to 0 as soon as a solve reaches.
+    #[inline]
+    pub fn frames_unreached(&self) -> u32 {
+        self.frames_unreached
+    }
 
-    pub fn new(node_manager: &mut NodeManager, renderer: &mut Renderer) -> Self {
-        let mut polygons = PolygonManager::default();
+    /// Get the end node of the limb
+    #[inline]
+    pub fn foot<'a>(&self, node_manager: &'a NodeManager) -> Option<&'a Node> {
+        self.ik.tip().and_then(|id| node_manager.get_node(id))
+    }
 
-        let body_nodes = node_manager.insert_nodes(&[
-            Node::new(24.),
-            Node::new(30.),
-            Node::new(30.),
-            Node::new(40.),
-            Node::new(45.),
-            Node::new(50.),
-            //
-            Node::new(40.),
-            //
-            Node::new(45.),
-            Node::new(50.),
-            Node::new(40.),
-            Node::new(38.),
-            Node::new(30.),
-            Node::new(22.),
-            Node::new(18.),
-            Node::new(10.),
-            Node::new(10.),
-            Node::new(10.),
-            Node::new(10.),
-        ]);
+    pub fn render(&mut self, node_manager: &NodeManager, renderer: &mut dyn RenderContext) {
+        if let Some(target) = self.ik.resolve_target(node_manager) {
+            renderer.prep_circle(
+                CircleInstance::new(target, 5.).with_color(glam::vec4(0., 1., 0., 1.)),
+            );
+        }
 
-        polygons.with_custom(vec![
-            (body_nodes[17], PolygonNode::color((0.2, 0.1, 0.0, 1.))),
-            (body_nodes[16], PolygonNode::color((0.2, 0.1, 0.0, 1.))),
-            (body_nodes[15], PolygonNode::color((0.2, 0.1, 0.0, 1.))),
-            (body_nodes[14], PolygonNode::color((0.2, 0.1, 0.0, 1.))),
-            (body_nodes[13], PolygonNode::color((0.3, 0.1, 0.0, 1.))),
-            (body_nodes[12], PolygonNode::color((0.3, 0.2, 0.0, 1.))),
-            (body_nodes[11], PolygonNode::color((0.3, 0.2, 0.0, 1.))),
-            (body_nodes[10], PolygonNode::color((0.3, 0.2, 0.0, 1.))),
-            (body_nodes[9], PolygonNode::color((0.2, 0.3, 0.0, 1.))),
-            (body_nodes[8], PolygonNode::color((0.2, 0.3, 0.0, 1.))),
-            (body_nodes[7], PolygonNode::color((0.2, 0.4, 0.0, 1.))),
-            (body_nodes[6], PolygonNode::color((0.2, 0.4, 0.0, 1.))),
-        ]);
+        let (vertices, indices) =
+            self.polygons
+                .calculate_vertices(node_manager, &self.ik.nodes[1..], None, None, None);
 
-        let arm_parent = body_nodes[5];
+        renderer.update_polygon(&mut self.instance, &vertices, &indices);
+    }
+}
 
-        let arm_right = CreatureLimb::new(
-            node_manager,
-            renderer,
-            arm_parent,
-            &[
-                Node::locked(20., 90_f32.to_radians()),
-                Node::angles(50., -50_f32.to_radians(), f32::consts::PI),
-                Node::angles(50., -50_f32.to_radians(), f32::consts::PI),
-                Node::angles(50., -50_f32.to_radians(), f32::consts::PI),
-            ],
-            HashMap::from([
-                (4, PolygonNode::radius(20.)),
-                (3, PolygonNode::radius(20.)),
-                (2, PolygonNode::radius(25.)),
-            ]),
-            150.,
-            -50_f32.to_radians(),
-            Self::CREATURE_LIMB_COLOR,
-        );
+impl CreatureSubstate {
+    const GROUND_COLOR: glam::Vec4 = glam::vec4(0.4, 0.4, 0.4, 1.);
+    const GROUND_Y: f32 = -300.;
+    /// Draw order of the ground and body. Limbs use the default layer 0
+    const GROUND_LAYER: i32 = -1;
+    const BODY_LAYER: i32 = 1;
 
-        let arm_left = CreatureLimb::new(
-            node_manager,
-            renderer,
-            arm_parent,
-            &[
-                Node::locked(20., -90_f32.to_radians()),
-                Node::angles(50., -f32::consts::PI, 50_f32.to_radians()),
-                Node::angles(50., -f32::consts::PI, 50_f32.to_radians()),
-                Node::angles(50., -f32::consts::PI, 50_f32.to_radians()),
-            ],
-            HashMap::from([
-                (4, PolygonNode::radius(20.)),
-                (3, PolygonNode::radius(20.)),
-                (2, PolygonNode::radius(25.)),
-            ]),
-            150.,
-            50_f32.to_radians(),
-            Self::CREATURE_LIMB_COLOR,
-        );
+    #[inline]
+    pub fn new(node_manager: &mut NodeManager, renderer: &mut dyn RenderContext) -> Self {
+        Self::from_spec(&CreatureSpec::load_default(), node_manager, renderer)
+    }
 
-        let leg_parent = body_nodes[9];
+    pub fn from_spec(
+        spec: &CreatureSpec,
+        node_manager: &mut NodeManager,
+        renderer: &mut dyn RenderContext,
+    ) -> Self {
+        // Ground draws under everything else
+        let ground_y = Some(Self::GROUND_Y);
+        let ground = ground_y.map(|ground_y| {
+            let (vertices, indices) = ground_vertices(ground_y, Self::GROUND_COLOR);
+            let mut ground = renderer.new_polygon(&vertices, &indices);
+            ground.set_layer(Self::GROUND_LAYER);
+            ground
+        });
 
-        let leg_right = CreatureLimb::new(
-            node_manager,
-            renderer,
-            leg_parent,
-            &[
-                Node::locked(20., 90_f32.to_radians()),
-                Node::angles(50., -50_f32.to_radians(), f32::consts::PI),
-                Node::angles(50., -50_f32.to_radians(), f32::consts::PI),
-                Node::angles(50., -50_f32.to_radians(), f32::consts::PI),
-            ],
-            HashMap::from([
-                (4, PolygonNode::radius(20.)),
-                (3, PolygonNode::radius(20.)),
-                (2, PolygonNode::radius(25.)),
-            ]),
-            140.,
-            -50_f32.to_radians(),
-            Self::CREATURE_LIMB_COLOR,
-        );
+        let mut polygons = PolygonManager::default();
+        polygons.with_base_color(spec.body_color.into());
 
-        let leg_left = CreatureLimb::new(
-            node_manager,
-            renderer,
-            leg_parent,
-            &[
-                Node::locked(20., -90_f32.to_radians()),
-                Node::angles(50., -f32::consts::PI, 50_f32.to_radians()),
-                Node::angles(50., -f32::consts::PI, 50_f32.to_radians()),
-                Node::angles(50., -f32::consts::PI, 50_f32.to_radians()),
-            ],
-            HashMap::from([
-                (4, PolygonNode::radius(20.)),
-                (3, PolygonNode::radius(20.)),
-                (2, PolygonNode::radius(25.)),
-            ]),
-            140.,
-            50_f32.to_radians(),
-            Self::CREATURE_LIMB_COLOR,
+        let (body, chains) = spec.insert_body(node_manager);
+
+        polygons.with_custom(
+            spec.body
+                .iter()
+                .zip(&body.nodes)
+                .filter_map(|(node, id)| Some((*id, PolygonNode::color(node.color?))))
+                .collect(),
         );
 
-        let body = ForwardKinematic { nodes: body_nodes };
+        let mut limbs = Vec::new();
+
+        chains.into_iter().for_each(|chain| {
+            let limb = chain.spec;
+            let mut creature_limb = CreatureLimb::new(
+                node_manager,
+                renderer,
+                chain.parent,
+                &chain.nodes,
+                limb.widths
+                    .iter()
+                    .map(|(index, radius)| (*index, PolygonNode::radius(*radius)))
+                    .collect(),
+                limb.reach_range,
+                limb.reach_angle.to_radians(),
+                limb.color.into(),
+            );
+            creature_limb.grabs = limb.grabs;
+            creature_limb.pole_angle = limb.pole_angle.map(f32::to_radians);
+
+            let mirrored = limb
+                .mirrored
+                .then(|| creature_limb.mirrored(node_manager, renderer, Axis::X));
+
+            limbs.push(creature_limb);
+            limbs.extend(mirrored);
+        });
 
-        // Create body after arms to draw on top
-        let body_poly_data = polygons.calculate_vertices(
-            node_manager,
-            &body.nodes,
-            Self::CREATURE_BODY_COLOR,
-            None,
-            None,
-        );
-        let polygon_body = renderer.polygon_pipeline.new_polygon(
-            &renderer.device,
-            &body_poly_data.0,
-            &body_poly_data.1,
-        );
+        // Body draws on top of the limbs
+        let body_poly_data =
+            polygons.calculate_vertices(node_manager, &body.nodes, None, None, None);
+        let mut polygon_body = renderer.new_polygon(&body_poly_data.0, &body_poly_data.1);
+        polygon_body.set_layer(Self::BODY_LAYER);
 
         Self {
             body,
+            spring: ChainSpring::new(40.),
+            tail_start: spec.tail_start,
+            scale: 1.,
+            ground_y,
+            _ground: ground,
+            limb_collision: true,
             prev_mouse_pos: glam::Vec2::ZERO,
             prev_mouse_delta: glam::Vec2::ZERO,
+            wind: Wind {
+                base: glam::vec2(20., 0.),
+                gust_amplitude: 30.,
+                gust_frequency: 0.5,
+            },
+            elapsed: 0.,
 
             polygons,
             polygon_body,
-            arm_right,
-            arm_left,
-            leg_right,
-            leg_left,
+            limbs,
         }
     }
 
-    pub fn update(&mut self, node_manager: &mut NodeManager, mouse_pos: glam::Vec2) {
-        let node = node_manager.get_node_mut(&self.body.nodes[0]).unwrap();
-        node.pos = mouse_pos;
+    const MIN_SCALE: f32 = 0.25;
+    const MAX_SCALE: f32 = 4.;
+
+    /// Grabbing limbs reach towards the mouse while held, and return to resting once released
+    fn update_grab(&mut self, mouse_pos: glam::Vec2, held: bool) {
+        let grab = held.then_some(mouse_pos);
+        self.limbs
+            .iter_mut()
+            .filter(|limb| limb.grabs)
+            .for_each(|limb| limb.reach_target = grab);
+    }
+}
 
+impl Substate for CreatureSubstate {
+    fn update(
+        &mut self,
+        time: SimTime,
+        node_manager: &mut NodeManager,
+        mouse_pos: glam::Vec2,
+        mouse_buttons: &Input<MouseButton>,
+        _keys: &Input<KeyCode>,
+    ) {
+        // Measure the speed over this step only, keeping the last turning direction otherwise
         let mouse_delta = mouse_pos - self.prev_mouse_pos;
-        let delta_len = mouse_delta.length();
+        self.prev_mouse_pos = mouse_pos;
 
-        if delta_len > 1. {
-            node.rotation = mouse_delta.to_angle();
-            self.prev_mouse_pos = mouse_pos;
+        if head_should_turn(mouse_delta, time.delta_seconds()) {
             self.prev_mouse_delta = mouse_delta;
         }
 
-        ik::process_fk(node_manager, &self.body);
+        self.update_grab(mouse_pos, mouse_buttons.pressed(MouseButton::Left));
+
+        // Blow the tail around before it gets reattached
+        self.elapsed += time.delta_seconds();
+        if let Some(tail) = self.body.nodes.get(self.tail_start..) {
+            self.wind
+                .apply(node_manager, tail, self.elapsed, time.delta_seconds());
+        }
+
+        self.spring.process(
+            node_manager,
+            &self.body,
+            Some((mouse_pos, self.prev_mouse_delta.to_angle())),
+            time.delta_seconds(),
+        );
 
-        self.arm_right.update(node_manager);
-        self.arm_left.update(node_manager);
-        self.leg_right.update(node_manager);
-        self.leg_left.update(node_manager);
+        self.limbs
+            .iter_mut()
+            .for_each(|limb| limb.update(time, node_manager, self.ground_y));
+
+        if self.limb_collision {
+            self.limbs.iter().for_each(|limb| {
+                // Skip the limb root and first joint which sit on the body
+                if let Some(limb_nodes) = limb.ik.nodes.get(2..) {
+                    ik::push_out_of_nodes(node_manager, limb_nodes, &self.body.nodes);
+                }
+            });
+        }
     }
 
-    pub fn render(&mut self, node_manager: &NodeManager, renderer: &mut Renderer) {
-        let head = node_manager.get_node(&self.body.nodes[0]).unwrap();
+    fn render(
+        &mut self,
+        node_manager: &NodeManager,
+        renderer: &mut dyn RenderContext,
+        _mouse_pos: glam::Vec2,
+    ) {
+        let Some(head) = self.body.root().and_then(|id| node_manager.get_node(id)) else {
+            return;
+        };
 
-        renderer.circle_pipeline.prep_circle(
+        renderer.prep_circle(
             CircleInstance::new(
                 head.pos + (self.prev_mouse_delta.normalize_or_zero() * 20.),
                 5.,
@@ -484,102 +925,907 @@ impl CreatureSubstate {
             .with_color(glam::vec4(1., 0., 0., 1.)),
         );
 
-        let body_poly_data = self.polygons.calculate_vertices(
-            node_manager,
-            &self.body.nodes,
-            Self::CREATURE_BODY_COLOR,
-            None,
-            None,
-        );
-        self.polygon_body.update(
-            &renderer.device,
-            &renderer.queue,
-            &body_poly_data.0,
-            &body_poly_data.1,
+        let body_poly_data =
+            self.polygons
+                .calculate_vertices(node_manager, &self.body.nodes, None, None, None);
+        renderer.update_polygon(&mut self.polygon_body, &body_poly_data.0, &body_poly_data.1);
+
+        if let Some(ground_y) = self.ground_y {
+            self.limbs
+                .iter()
+                .filter_map(|limb| limb.foot(node_manager))
+                .filter_map(|foot| foot_shadow(foot.pos, ground_y))
+                .for_each(|shadow| renderer.prep_circle(shadow));
+        }
+
+        self.limbs
+            .iter_mut()
+            .for_each(|limb| limb.render(node_manager, renderer));
+    }
+
+    fn chains(&self) -> Vec<&[NodeID]> {
+        std::iter::once(self.body.nodes.as_slice())
+            .chain(self.limbs.iter().map(|limb| &limb.ik.nodes[1..]))
+            .collect()
+    }
+
+    fn report_chains(&self, registry: &mut ChainRegistry) {
+        registry.register_fk("creature body", &self.body);
+        self.limbs
+            .iter()
+            .for_each(|limb| registry.register_ik("creature limb", &limb.ik, limb.last_result));
+    }
+
+    fn render_outlines(&self, node_manager: &NodeManager, renderer: &mut dyn RenderContext) {
+        self.limbs.iter().for_each(|limb| {
+            renderer.prep_strip(
+                &limb
+                    .polygons
+                    .outline_path(node_manager, &limb.ik.nodes[1..], &[]),
+                OUTLINE_COLOR,
+            )
+        });
+        renderer.prep_strip(
+            &self
+                .polygons
+                .outline_path(node_manager, &self.body.nodes, &[]),
+            OUTLINE_COLOR,
         );
+    }
 
-        self.arm_right.render(node_manager, renderer);
-        self.arm_left.render(node_manager, renderer);
-        self.leg_right.render(node_manager, renderer);
-        self.leg_left.render(node_manager, renderer);
+    #[inline]
+    fn clear_color(&self) -> glam::Vec4 {
+        glam::vec4(0.1, 0.14, 0.12, 1.)
     }
+
+    #[inline]
+    fn scale(&self) -> Option<f32> {
+        Some(self.scale)
+    }
+
+    /// Resize the whole creature around its head, relative to the size it was created at
+    fn set_scale(&mut self, node_manager: &mut NodeManager, scale: f32) {
+        let scale = scale.clamp(CreatureSubstate::MIN_SCALE, CreatureSubstate::MAX_SCALE);
+        let factor = scale / self.scale;
+        self.scale = scale;
+
+        let nodes = self
+            .body
+            .nodes
+            .iter()
+            .chain(self.limbs.iter().flat_map(|limb| &limb.ik.nodes[1..]))
+            .copied()
+            .collect::<Vec<_>>();
+        node_manager.scale_nodes(&nodes, factor);
+
+        self.limbs.iter_mut().for_each(|limb| limb.scale(factor));
+    }
+}
+
+/// Keep a position from going below the ground line
+#[inline]
+fn clamp_to_ground(pos: glam::Vec2, ground_y: f32) -> glam::Vec2 {
+    glam::vec2(pos.x, pos.y.max(ground_y))
+}
+
+/// Create a shadow on the ground under a foot that shrinks and fades as the foot rises.
+/// Returns None if the foot is too high to cast a shadow.
+fn foot_shadow(foot: glam::Vec2, ground_y: f32) -> Option<CircleInstance> {
+    const MAX_HEIGHT: f32 = 150.;
+
+    let strength = 1. - ((foot.y - ground_y) / MAX_HEIGHT).clamp(0., 1.);
+    if strength <= 0. {
+        return None;
+    }
+
+    Some(
+        CircleInstance::new(glam::vec2(foot.x, ground_y), 6. + 10. * strength)
+            .with_color(glam::vec4(0., 0., 0., 0.5 * strength))
+            .with_border(0., glam::Vec4::ZERO),
+    )
+}
+
+/// Create a wide thin rectangle with its top edge along the ground line
+fn ground_vertices(ground_y: f32, color: glam::Vec4) -> (Vec<PolygonVertex>, Vec<u16>) {
+    const HALF_WIDTH: f32 = 10000.;
+    const THICKNESS: f32 = 4.;
+
+    let vertices = [
+        glam::vec2(-HALF_WIDTH, ground_y),
+        glam::vec2(HALF_WIDTH, ground_y),
+        glam::vec2(-HALF_WIDTH, ground_y - THICKNESS),
+        glam::vec2(HALF_WIDTH, ground_y - THICKNESS),
+    ]
+    .into_iter()
+    .map(|pos| PolygonVertex {
+        pos,
+        depth: 0.,
+        t: 0.,
+        color,
+    })
+    .collect();
+
+    (vertices, vec![0, 2, 1, 1, 2, 3])
 }
 
 pub struct BridgeSubstate {
     ik: InverseKinematic,
+    last_result: SolveResult,
     gravity: glam::Vec2,
     gravity_angle: f32,
+    pub physics: ChainPhysics,
+    pub wind: Wind,
+    elapsed: f32,
 
+    /// Number of rendered points between each node. Rendering only, physics is unaffected.
+    pub smoothing: Option<usize>,
+    polygons: PolygonManager,
     instance: PolygonInstance,
 }
 
 impl BridgeSubstate {
-    pub fn new(node_manager: &mut NodeManager, renderer: &mut Renderer) -> Self {
+    /// Acceleration the bridge falls at
+    const GRAVITY: f32 = 300.;
+
+    pub fn new(node_manager: &mut NodeManager, renderer: &mut dyn RenderContext) -> Self {
         let nodes = node_manager.insert_nodes(&[Node::unlocked(20.); 35]);
 
-        let ik = InverseKinematic {
-            nodes,
-            anchor: Some(glam::vec2(-300., 0.)),
-            target: glam::Vec2::ZERO,
-            cycles: 10,
-        };
+        // Long chain, so allow the end to sit a little further from the target
+        let ik = InverseKinematic::new(nodes, Some(glam::vec2(-300., 0.)), glam::Vec2::ZERO)
+            .with_tolerance(10.);
 
         let gravity_angle = -90_f32.to_radians();
-        let gravity = glam::Vec2::from_angle(gravity_angle) * 300.;
+        let gravity = glam::Vec2::from_angle(gravity_angle) * Self::GRAVITY;
 
-        let (vertices, indices) = PolygonManager::default().calculate_vertices(
+        let mut polygons = PolygonManager::default();
+        polygons.with_radius_profile(polygon_manager::thickest_in_middle);
+
+        let (vertices, indices) = polygons.calculate_vertices(
             &node_manager,
             &ik.nodes,
-            glam::vec4(0.322, 0.231, 0., 1.),
+            Some(glam::vec4(0.322, 0.231, 0., 1.)),
             None,
             None,
         );
 
-        let instance = renderer
-            .polygon_pipeline
-            .new_polygon(&renderer.device, &vertices, &indices);
+        let instance = renderer.new_polygon(&vertices, &indices);
 
         Self {
             ik,
+            last_result: SolveResult::default(),
             gravity,
             gravity_angle,
+            physics: ChainPhysics::new(8, 0.02),
+            wind: Wind {
+                base: glam::vec2(40., 0.),
+                gust_amplitude: 80.,
+                gust_frequency: 0.3,
+            },
+            elapsed: 0.,
+            smoothing: Some(4),
+            polygons,
             instance,
         }
     }
 
-    pub fn update(&mut self, time: &Time, node_manager: &mut NodeManager, mouse_pos: glam::Vec2) {
-        self.ik.nodes.iter().skip(1).for_each(|id| {
-            let node = node_manager.get_node_mut(id).unwrap();
-            node.pos += self.gravity * time.delta_seconds();
-        });
+    /// Position of the post the bridge is pinned to, if it has one
+    #[inline]
+    pub fn anchor_marker(&self) -> Option<glam::Vec2> {
+        self.ik.anchor
+    }
+}
+
+impl Substate for BridgeSubstate {
+    fn update(
+        &mut self,
+        time: SimTime,
+        node_manager: &mut NodeManager,
+        mouse_pos: glam::Vec2,
+        _mouse_buttons: &Input<MouseButton>,
+        _keys: &Input<KeyCode>,
+    ) {
+        self.physics.step(
+            node_manager,
+            &self.ik.nodes,
+            self.gravity,
+            time.delta_seconds(),
+        );
 
-        self.ik.target = mouse_pos;
+        self.elapsed += time.delta_seconds();
+        self.wind.apply(
+            node_manager,
+            &self.ik.nodes[1..],
+            self.elapsed,
+            time.delta_seconds(),
+        );
+
+        self.ik.target = mouse_pos.into();
 
-        ik::fabrik(node_manager, &self.ik);
+        self.last_result = self.ik.solve(node_manager);
 
         self.gravity_angle += 0.5 * time.delta_seconds();
-        self.gravity = glam::Vec2::from_angle(self.gravity_angle) * 300.;
+        self.gravity = glam::Vec2::from_angle(self.gravity_angle) * Self::GRAVITY;
     }
 
-    pub fn render(
+    fn render(
         &mut self,
         node_manager: &NodeManager,
-        renderer: &mut Renderer,
+        renderer: &mut dyn RenderContext,
         mouse_pos: glam::Vec2,
     ) {
         renderer
-            .circle_pipeline
             .prep_circle(CircleInstance::new(mouse_pos, 5.).with_color(glam::vec4(1., 0., 0., 1.)));
 
-        let (vertices, indices) = PolygonManager::default().calculate_vertices(
+        if let Some(anchor) = self.anchor_marker() {
+            renderer.prep_circle(
+                CircleInstance::new(anchor, 12.)
+                    .with_color(glam::vec4(0.3, 0.3, 0.3, 1.))
+                    .with_border(4., glam::vec4(0.1, 0.1, 0.1, 1.)),
+            );
+        }
+
+        let color = Some(glam::vec4(0.349, 0.278, 0.098, 1.));
+
+        let (vertices, indices) = match self.smoothing {
+            Some(subdivisions) => self.polygons.calculate_smoothed_vertices(
+                node_manager,
+                &self.ik.nodes[1..],
+                subdivisions,
+                color,
+            ),
+            None => self.polygons.calculate_vertices(
+                node_manager,
+                &self.ik.nodes[1..],
+                color,
+                None,
+                None,
+            ),
+        };
+
+        renderer.update_polygon(&mut self.instance, &vertices, &indices);
+    }
+
+    #[inline]
+    fn chains(&self) -> Vec<&[NodeID]> {
+        vec![&self.ik.nodes]
+    }
+
+    #[inline]
+    fn report_chains(&self, registry: &mut ChainRegistry) {
+        registry.register_ik("bridge", &self.ik, self.last_result);
+    }
+
+    fn render_outlines(&self, node_manager: &NodeManager, renderer: &mut dyn RenderContext) {
+        renderer.prep_strip(
+            &self
+                .polygons
+                .outline_path(node_manager, &self.ik.nodes, &[]),
+            OUTLINE_COLOR,
+        );
+    }
+
+    #[inline]
+    fn clear_color(&self) -> glam::Vec4 {
+        glam::vec4(0.14, 0.12, 0.1, 1.)
+    }
+}
+
+/// Scales applied to positions to get each mirrored copy, starting with the original
+const MIRRORS: [glam::Vec2; 4] = [
+    glam::vec2(1., 1.),
+    glam::vec2(-1., 1.),
+    glam::vec2(1., -1.),
+    glam::vec2(-1., -1.),
+];
+
+/// Get the reflections of a point across the x axis, y axis and both axes through the origin
+#[inline]
+pub fn mirror_images(point: glam::Vec2) -> [glam::Vec2; 3] {
+    [point * MIRRORS[1], point * MIRRORS[2], point * MIRRORS[3]]
+}
+
+pub struct MirrorSubstate {
+    ik: InverseKinematic,
+    last_result: SolveResult,
+
+    polygons: PolygonManager,
+    instances: Vec<PolygonInstance>,
+}
+
+impl MirrorSubstate {
+    const COLORS: [glam::Vec4; 4] = [
+        glam::vec4(0.8, 0.2, 0.4, 1.),
+        glam::vec4(0.2, 0.4, 0.8, 1.),
+        glam::vec4(0.4, 0.8, 0.2, 1.),
+        glam::vec4(0.8, 0.6, 0.2, 1.),
+    ];
+
+    pub fn new(node_manager: &mut NodeManager, renderer: &mut dyn RenderContext) -> Self {
+        let nodes = node_manager.insert_nodes(&[Node::new(30.); 10]);
+
+        let ik = InverseKinematic::new(nodes, Some(glam::Vec2::ZERO), glam::Vec2::ZERO);
+
+        let polygons = PolygonManager::default();
+        let (vertices, indices) =
+            polygons.calculate_vertices(node_manager, &ik.nodes, None, None, None);
+
+        let instances = Self::COLORS
+            .iter()
+            .map(|color| {
+                let vertices = Self::transform_vertices(&vertices, glam::Vec2::ONE, *color);
+                renderer.new_polygon(&vertices, &indices)
+            })
+            .collect();
+
+        Self {
+            ik,
+            last_result: SolveResult::default(),
+            polygons,
+            instances,
+        }
+    }
+
+    fn transform_vertices(
+        vertices: &[PolygonVertex],
+        mirror: glam::Vec2,
+        color: glam::Vec4,
+    ) -> Vec<PolygonVertex> {
+        vertices
+            .iter()
+            .map(|vertex| PolygonVertex {
+                pos: vertex.pos * mirror,
+                color,
+                ..*vertex
+            })
+            .collect()
+    }
+}
+
+impl Substate for MirrorSubstate {
+    fn update(
+        &mut self,
+        _time: SimTime,
+        node_manager: &mut NodeManager,
+        mouse_pos: glam::Vec2,
+        _mouse_buttons: &Input<MouseButton>,
+        _keys: &Input<KeyCode>,
+    ) {
+        self.ik.target = mouse_pos.into();
+        self.last_result = self.ik.solve(node_manager);
+    }
+
+    fn render(
+        &mut self,
+        node_manager: &NodeManager,
+        renderer: &mut dyn RenderContext,
+        mouse_pos: glam::Vec2,
+    ) {
+        std::iter::once(mouse_pos)
+            .chain(mirror_images(mouse_pos))
+            .for_each(|target| {
+                renderer.prep_circle(
+                    CircleInstance::new(target, 5.).with_color(glam::vec4(1., 0., 0., 1.)),
+                )
+            });
+
+        let (vertices, indices) =
+            self.polygons
+                .calculate_vertices(node_manager, &self.ik.nodes, None, None, None);
+
+        self.instances
+            .iter_mut()
+            .zip(MIRRORS.iter().zip(Self::COLORS.iter()))
+            .for_each(|(instance, (mirror, color))| {
+                let vertices = Self::transform_vertices(&vertices, *mirror, *color);
+                renderer.update_polygon(instance, &vertices, &indices);
+            });
+    }
+
+    #[inline]
+    fn chains(&self) -> Vec<&[NodeID]> {
+        vec![&self.ik.nodes]
+    }
+
+    #[inline]
+    fn report_chains(&self, registry: &mut ChainRegistry) {
+        registry.register_ik("mirror", &self.ik, self.last_result);
+    }
+
+    fn render_outlines(&self, node_manager: &NodeManager, renderer: &mut dyn RenderContext) {
+        let outline = self
+            .polygons
+            .outline_path(node_manager, &self.ik.nodes, &[]);
+        MIRRORS.iter().for_each(|axis| {
+            let points = outline
+                .iter()
+                .map(|point| *point * *axis)
+                .collect::<Vec<_>>();
+            renderer.prep_strip(&points, OUTLINE_COLOR);
+        });
+    }
+
+    #[inline]
+    fn clear_color(&self) -> glam::Vec4 {
+        glam::vec4(0.05, 0.05, 0.1, 1.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::NullRenderer;
+
+    #[test]
+    fn head_turn_threshold_ignores_frame_rate() {
+        // Same mouse speed sampled at 60 and 120fps
+        for speed in [HEAD_TURN_SPEED * 0.5, HEAD_TURN_SPEED * 2.] {
+            let at_60 = head_should_turn(glam::vec2(speed / 60., 0.), 1. / 60.);
+            let at_120 = head_should_turn(glam::vec2(speed / 120., 0.), 1. / 120.);
+            assert_eq!(at_60, at_120);
+            assert_eq!(at_60, speed > HEAD_TURN_SPEED);
+        }
+
+        assert!(!head_should_turn(glam::vec2(100., 0.), 0.));
+    }
+
+    #[test]
+    fn creature_builds_every_limb_in_its_spec() {
+        let spec = CreatureSpec::load_default();
+        let mut node_manager = NodeManager::default();
+        let creature =
+            CreatureSubstate::from_spec(&spec, &mut node_manager, &mut NullRenderer::default());
+
+        assert_eq!(node_manager.get_values().count(), spec.node_count());
+        assert_eq!(creature.body.nodes.len(), spec.body.len());
+        assert_eq!(creature.limbs.len(), spec.limb_count());
+
+        // Each mirrored copy follows its limb, reaching to the other side
+        creature
+            .limbs
+            .chunks(2)
+            .zip(&spec.limbs)
+            .for_each(|(pair, limb)| {
+                assert_eq!(pair[0].limb_reach_angle, limb.reach_angle.to_radians());
+                assert_eq!(pair[1].limb_reach_angle, -pair[0].limb_reach_angle);
+                assert_eq!(pair[1].ik.nodes[0], pair[0].ik.nodes[0]);
+                assert_eq!(pair[1].grabs, limb.grabs);
+                assert_eq!(pair[1].node_data.len(), limb.nodes.len());
+            });
+    }
+
+    #[test]
+    fn targets_below_ground_are_clamped() {
+        let ground_y = -100.;
+
+        assert_eq!(
+            clamp_to_ground(glam::vec2(30., -180.), ground_y),
+            glam::vec2(30., ground_y)
+        );
+        assert_eq!(
+            clamp_to_ground(glam::vec2(30., -40.), ground_y),
+            glam::vec2(30., -40.)
+        );
+    }
+
+    #[test]
+    fn grounded_foot_casts_bigger_darker_shadow() {
+        let ground_y = -100.;
+        let grounded = foot_shadow(glam::vec2(0., ground_y), ground_y).unwrap();
+        let raised = foot_shadow(glam::vec2(0., ground_y + 60.), ground_y).unwrap();
+
+        assert!(grounded.radius() > raised.radius());
+        assert!(grounded.color().w > raised.color().w);
+
+        // Too high to cast a shadow at all
+        assert!(foot_shadow(glam::vec2(0., ground_y + 500.), ground_y).is_none());
+    }
+
+    #[test]
+    fn mirror_images_reflect_across_both_axes() {
+        assert_eq!(
+            mirror_images(glam::vec2(30., 20.)),
+            [
+                glam::vec2(-30., 20.),
+                glam::vec2(30., -20.),
+                glam::vec2(-30., -20.),
+            ]
+        );
+    }
+
+    #[test]
+    fn outline_colors_alternate_along_chain() {
+        let colors = (0..5).map(outline_color).collect::<Vec<_>>();
+
+        assert_ne!(colors[0], colors[1]);
+        colors.iter().enumerate().for_each(|(index, color)| {
+            assert_eq!(*color, colors[index % 2]);
+        });
+    }
+
+    #[test]
+    fn slow_mouse_keeps_head_direction() {
+        let mut node_manager = NodeManager::default();
+        let mut substate = FKSubstate::new(&mut node_manager, &mut NullRenderer::default());
+        let dt = 1. / 60.;
+        let mut update = |substate: &mut FKSubstate, mouse_pos: glam::Vec2| {
+            substate.update(
+                SimTime::new(dt),
+                &mut node_manager,
+                mouse_pos,
+                &Input::default(),
+                &Input::default(),
+            )
+        };
+
+        update(&mut substate, glam::vec2(100., 0.));
+        assert_eq!(substate.prev_mouse_delta, glam::vec2(100., 0.));
+
+        // Creeping upwards below the turn speed never builds up into a turn
+        let step = HEAD_TURN_SPEED * dt * 0.5;
+        for index in 1..=100 {
+            update(&mut substate, glam::vec2(100., step * index as f32));
+        }
+        assert_eq!(substate.prev_mouse_delta, glam::vec2(100., 0.));
+    }
+
+    #[test]
+    fn mirrored_limb_negates_reach_angle() {
+        let mut node_manager = NodeManager::default();
+        let mut renderer = NullRenderer::default();
+        let parent = node_manager.insert(Node::new(30.));
+
+        let limb = CreatureLimb::new(
+            &mut node_manager,
+            &mut renderer,
+            parent,
+            &[Node::angles(20., -0.8, 0.3), Node::locked(15., 0.4)],
+            HashMap::new(),
+            60.,
+            1.2,
+            glam::Vec4::ONE,
+        );
+        let mirrored = limb.mirrored(&mut node_manager, &mut renderer, Axis::X);
+
+        assert_eq!(mirrored.limb_reach_angle, -limb.limb_reach_angle);
+        assert_eq!(mirrored.ik.nodes[0], parent);
+        assert_eq!(mirrored.node_data[0].min_rotation, -0.3);
+        assert_eq!(mirrored.node_data[0].max_rotation, 0.8);
+        assert_eq!(mirrored.node_data[1].min_rotation, -0.4);
+        assert_eq!(mirrored.node_data[1].max_rotation, -0.4);
+    }
+
+    #[test]
+    fn bridge_marker_follows_anchor() {
+        let mut node_manager = NodeManager::default();
+        let mut bridge = BridgeSubstate::new(&mut node_manager, &mut NullRenderer::default());
+        assert_eq!(bridge.anchor_marker(), bridge.ik.anchor);
+        assert!(bridge.anchor_marker().is_some());
+
+        bridge.ik.anchor = Some(glam::vec2(15., -40.));
+        assert_eq!(bridge.anchor_marker(), Some(glam::vec2(15., -40.)));
+
+        bridge.ik.anchor = None;
+        assert_eq!(bridge.anchor_marker(), None);
+    }
+
+    #[test]
+    fn grabbing_limbs_track_mouse_only_while_held() {
+        let mut node_manager = NodeManager::default();
+        let mut creature = CreatureSubstate::new(&mut node_manager, &mut NullRenderer::default());
+        let mouse_pos = glam::vec2(40., 60.);
+        let time = SimTime::new(1. / 60.);
+
+        let step = |creature: &mut CreatureSubstate, node_manager: &mut NodeManager, held: bool| {
+            creature.update_grab(mouse_pos, held);
+            (0..30).for_each(|_| {
+                creature
+                    .limbs
+                    .iter_mut()
+                    .for_each(|limb| limb.update(time, node_manager, None))
+            });
+        };
+        let grabbing = |creature: &CreatureSubstate, node_manager: &NodeManager| {
+            creature
+                .limbs
+                .iter()
+                .filter(|limb| limb.grabs)
+                .map(|limb| (limb.reach_blend, limb.ik.target.resolve(node_manager)))
+                .collect::<Vec<_>>()
+        };
+
+        step(&mut creature, &mut node_manager, true);
+        let held = grabbing(&creature, &node_manager);
+        assert!(!held.is_empty());
+        held.iter().for_each(|(blend, target)| {
+            assert_eq!(*blend, 1.);
+            assert_eq!(*target, Some(mouse_pos));
+        });
+
+        // Released limbs blend back to their resting targets
+        step(&mut creature, &mut node_manager, false);
+        grabbing(&creature, &node_manager)
+            .iter()
+            .for_each(|(blend, target)| {
+                assert_eq!(*blend, 0.);
+                assert_ne!(*target, Some(mouse_pos));
+            });
+
+        // Limbs that don't grab never reach for the mouse
+        creature
+            .limbs
+            .iter()
+            .filter(|limb| !limb.grabs)
+            .for_each(|limb| assert_eq!(limb.reach_blend, 0.));
+    }
+
+    #[test]
+    fn bridge_steps_with_fixed_time() {
+        let mut node_manager = NodeManager::default();
+        let mut bridge = BridgeSubstate::new(&mut node_manager, &mut NullRenderer::default());
+        let positions = |node_manager: &NodeManager, bridge: &BridgeSubstate| {
+            bridge
+                .ik
+                .nodes
+                .iter()
+                .map(|id| node_manager.get_node(id).unwrap().pos)
+                .collect::<Vec<_>>()
+        };
+        let start = positions(&node_manager, &bridge);
+
+        (0..120).for_each(|_| {
+            bridge.update(
+                SimTime { dt: 1.0 / 60.0 },
+                &mut node_manager,
+                glam::vec2(200., -50.),
+                &Input::default(),
+                &Input::default(),
+            )
+        });
+
+        // Two seconds of fixed steps have passed
+        assert!((bridge.elapsed - 2.).abs() < 1e-4);
+
+        let end = positions(&node_manager, &bridge);
+        assert!(end.iter().all(|pos| pos.is_finite()));
+        assert_ne!(start, end);
+    }
+
+    #[test]
+    fn frames_unreached_counts_until_reached() {
+        let mut node_manager = NodeManager::default();
+        let parent = node_manager.insert(Node::new(30.));
+        let nodes = (0..3)
+            .map(|index| Node {
+                pos: glam::vec2(-20. * index as f32, 5. * index as f32),
+                ..Node::unlocked(20.)
+            })
+            .collect::<Vec<_>>();
+        let mut limb = CreatureLimb::new(
+            &mut node_manager,
+            &mut NullRenderer::default(),
+            parent,
+            &nodes,
+            HashMap::new(),
+            40.,
+            0.,
+            glam::Vec4::ONE,
+        );
+        let time = SimTime::new(1. / 60.);
+
+        // Fully blended towards a target far out of reach
+        limb.reach_target = Some(glam::vec2(500., 500.));
+        (0..30).for_each(|_| limb.update(time, &mut node_manager, None));
+        assert_eq!(limb.reach_blend, 1.);
+        let unreached = limb.frames_unreached();
+        assert!(unreached > 0);
+
+        limb.update(time, &mut node_manager, None);
+        assert_eq!(limb.frames_unreached(), unreached + 1);
+
+        // Straight to a target within reach
+        limb.reach_target = Some(glam::vec2(30., 20.));
+        limb.update(time, &mut node_manager, None);
+        assert_eq!(limb.frames_unreached(), 0);
+    }
+
+    #[test]
+    fn anchor_drag_lasts_while_held() {
+        let mut substate = IKSubstate::new(&mut NodeManager::default());
+
+        // Holding without grabbing first doesn't start a drag
+        substate.update_drag(false, true);
+        assert!(!substate.dragging_anchor);
+
+        substate.update_drag(true, true);
+        assert!(substate.dragging_anchor);
+
+        substate.update_drag(false, true);
+        assert!(substate.dragging_anchor);
+
+        substate.update_drag(false, false);
+        assert!(!substate.dragging_anchor);
+    }
+
+    #[test]
+    fn fixed_timestep_carries_partial_steps() {
+        let mut timestep = FixedTimestep::new(0.25, 8);
+
+        assert_eq!(timestep.advance(0.125), 0);
+        assert_eq!(timestep.advance(0.25), 1);
+        assert_eq!(timestep.advance(0.5), 2);
+        assert_eq!(timestep.advance(0.125), 1);
+        assert_eq!(timestep.advance(0.125), 0);
+    }
+
+    #[test]
+    fn fixed_timestep_drops_time_past_max_steps() {
+        let mut timestep = FixedTimestep::new(0.25, 2);
+
+        assert_eq!(timestep.advance(10.), 2);
+        assert_eq!(timestep.advance(0.), 0);
+        assert_eq!(timestep.advance(0.25), 1);
+    }
+
+    #[test]
+    fn fixed_timestep_ignores_invalid_time() {
+        let mut timestep = FixedTimestep::new(0.25, 8);
+        assert_eq!(timestep.advance(-1.), 0);
+        assert_eq!(timestep.advance(0.25), 1);
+
+        let mut timestep = FixedTimestep::new(0., 8);
+        assert_eq!(timestep.advance(1.), 0);
+    }
+
+    #[test]
+    fn substates_share_nodes_and_update_together() {
+        let mut node_manager = NodeManager::default();
+        let mut renderer = NullRenderer::default();
+        let constructors = default_substates();
+
+        let mut substates = [&constructors[0], &constructors[3]]
+            .map(|constructor| constructor(&mut node_manager, &mut renderer));
+
+        // Each substate keeps its own nodes in the shared manager
+        let nodes = substates
+            .iter()
+            .map(|substate| substate.chains().concat())
+            .collect::<Vec<_>>();
+        assert!(nodes[0].iter().all(|id| !nodes[1].contains(id)));
+
+        let start = nodes
+            .iter()
+            .map(|nodes| node_manager.get_node_positions(nodes))
+            .collect::<Vec<_>>();
+
+        substates.iter_mut().for_each(|substate| {
+            substate.update(
+                SimTime { dt: 1.0 / 60.0 },
+                &mut node_manager,
+                glam::vec2(150., 80.),
+                &Input::default(),
+                &Input::default(),
+            )
+        });
+
+        nodes.iter().zip(&start).for_each(|(nodes, start)| {
+            assert_ne!(&node_manager.get_node_positions(nodes), start);
+        });
+    }
+
+    /// Moves a single node towards the mouse
+    struct Follower {
+        node: NodeID,
+    }
+
+    impl Substate for Follower {
+        fn update(
+            &mut self,
+            time: SimTime,
+            node_manager: &mut NodeManager,
+            mouse_pos: glam::Vec2,
+            _mouse_buttons: &Input<MouseButton>,
+            _keys: &Input<KeyCode>,
+        ) {
+            let node = node_manager.get_node_mut(&self.node).unwrap();
+            node.pos += (mouse_pos - node.pos) * time.dt;
+        }
+
+        fn render(
+            &mut self,
+            node_manager: &NodeManager,
+            renderer: &mut dyn RenderContext,
+            _mouse_pos: glam::Vec2,
+        ) {
+            let node = node_manager.get_node(&self.node).unwrap();
+            renderer.prep_circle(CircleInstance::new(node.pos, node.radius));
+        }
+
+        fn chains(&self) -> Vec<&[NodeID]> {
+            vec![std::slice::from_ref(&self.node)]
+        }
+    }
+
+    #[test]
+    fn custom_substate_runs_through_trait() {
+        let mut node_manager = NodeManager::default();
+        let node = node_manager.insert(Node::new(10.));
+        let mut substate: Box<dyn Substate> = Box::new(Follower { node });
+
+        substate.update(
+            SimTime { dt: 0.5 },
+            &mut node_manager,
+            glam::vec2(100., 0.),
+            &Input::default(),
+            &Input::default(),
+        );
+        substate.render(
             &node_manager,
-            &self.ik.nodes[1..],
-            glam::vec4(0.349, 0.278, 0.098, 1.),
-            None,
-            None,
+            &mut NullRenderer::default(),
+            glam::Vec2::ZERO,
+        );
+        assert_eq!(
+            node_manager.get_node(&node).unwrap().pos,
+            glam::vec2(50., 0.)
+        );
+        assert_eq!(substate.chains(), [[node].as_slice()]);
+
+        // The default trait methods work off the custom chains
+        substate.remove_nodes(&mut node_manager);
+        assert!(!node_manager.contains(&node));
+    }
+
+    #[test]
+    fn pin_toggles_and_moves() {
+        let mut substate = IKSubstate::new(&mut NodeManager::default());
+        let (a, b) = (glam::vec2(10., 20.), glam::vec2(-30., 40.));
+
+        // Clicking does nothing while following the mouse
+        substate.update_pin(a, true, false);
+        assert_eq!(substate.pinned_target, None);
+
+        substate.update_pin(a, false, true);
+        assert_eq!(substate.pinned_target, Some(a));
+
+        // Moving the mouse alone leaves the pin in place
+        substate.update_pin(b, false, false);
+        assert_eq!(substate.pinned_target, Some(a));
+
+        substate.update_pin(b, true, false);
+        assert_eq!(substate.pinned_target, Some(b));
+
+        substate.update_pin(a, false, true);
+        assert_eq!(substate.pinned_target, None);
+    }
+
+    #[test]
+    fn planted_foot_holds_until_a_step_away() {
+        let mut node_manager = NodeManager::default();
+        let parent = node_manager.insert(Node::new(30.));
+        let mut limb = CreatureLimb::new(
+            &mut node_manager,
+            &mut NullRenderer::default(),
+            parent,
+            &[Node::unlocked(20.), Node::unlocked(20.)],
+            HashMap::new(),
+            60.,
+            0.,
+            glam::Vec4::ONE,
         );
 
-        self.instance
-            .update(&renderer.device, &renderer.queue, &vertices, &indices);
+        // A reach range of 60 gives a step threshold of 30
+        limb.update_step(glam::Vec2::ZERO);
+        assert_eq!(limb.planted_pos, Some(glam::Vec2::ZERO));
+
+        limb.update_step(glam::vec2(25., 0.));
+        assert_eq!(limb.planted_pos, Some(glam::Vec2::ZERO));
+        assert_eq!(limb.rest_target, glam::Vec2::ZERO);
+
+        // Past the threshold the foot lifts and steps beyond the resting position
+        limb.update_step(glam::vec2(40., 0.));
+        assert_eq!(limb.planted_pos, None);
+        assert_eq!(limb.rest_target, glam::vec2(55., 0.));
+
+        limb.update_step(glam::vec2(45., 0.));
+        assert_eq!(limb.planted_pos, Some(glam::vec2(55., 0.)));
     }
 }
@@ -0,0 +1,49 @@
+use ik_creatures_v2::{default_substates, NodeManager, NullRenderer, SimTime};
+use roots_core::common::input::Input;
+
+#[test]
+fn default_substates_step_without_gpu() {
+    let time = SimTime::new(1. / 60.);
+    let target = glam::vec2(150., 100.);
+
+    default_substates()
+        .into_iter()
+        .enumerate()
+        .for_each(|(index, constructor)| {
+            let mut node_manager = NodeManager::new();
+            let mut renderer = NullRenderer::default();
+            let mut substate = constructor(&mut node_manager, &mut renderer);
+            let polygons = renderer.polygons;
+
+            let ids = substate.chains().concat();
+            assert!(!ids.is_empty(), "substate {} has no nodes", index);
+            let start = node_manager.get_node_positions(&ids);
+
+            (0..60).for_each(|_| {
+                substate.update(
+                    time,
+                    &mut node_manager,
+                    target,
+                    &Input::default(),
+                    &Input::default(),
+                );
+                substate.render(&node_manager, &mut renderer, target);
+            });
+
+            let end = node_manager.get_node_positions(&ids);
+            assert!(
+                end.iter().all(|pos| pos.is_finite()),
+                "substate {} has non finite positions",
+                index
+            );
+            assert_ne!(start, end, "substate {} didn't move", index);
+
+            // Polygons are made once up front then updated in place each frame
+            assert_eq!(
+                renderer.polygons, polygons,
+                "substate {} leaked polygons",
+                index
+            );
+            assert!(renderer.circles >= 60, "substate {} drew no circles", index);
+        });
+}
@@ -0,0 +1,31 @@
+use ik_creatures_v2::{fabrik, InverseKinematic, NodeManager};
+
+#[test]
+fn fabrik_reaches_target_without_renderer() {
+    let mut node_manager = NodeManager::new();
+    let nodes = node_manager.insert_chain(6, 30., 20.);
+
+    // Start from a gentle curl, as a perfectly straight chain has no bend for FABRIK to grow
+    let mut pos = glam::Vec2::ZERO;
+    nodes.iter().enumerate().for_each(|(index, id)| {
+        let node = node_manager.get_node_mut(id).unwrap();
+        node.pos = pos;
+        pos -= glam::Vec2::from_angle(index as f32 * 0.2) * node.segment_length();
+    });
+
+    let target = glam::vec2(-110., 30.);
+    let ik = InverseKinematic::builder(nodes.clone())
+        .anchor(glam::Vec2::ZERO)
+        .target(target)
+        .cycles(40)
+        .build();
+
+    assert!(ik.is_reachable(&node_manager));
+    assert!(fabrik(&mut node_manager, &ik));
+
+    let tip = node_manager.get_node(nodes.last().unwrap()).unwrap();
+    assert!(tip.pos.distance(target) < ik.tolerance);
+
+    let root = node_manager.get_node(&nodes[0]).unwrap();
+    assert_eq!(root.pos, glam::Vec2::ZERO);
+}